@@ -0,0 +1,164 @@
+// Times the queries the spatial index speeds up on a crowded late game 4 player state, against the full scans they
+// replaced. Run with `cargo bench --bench spatial`
+extern crate memetron_420;
+
+use std::time::Instant;
+
+use memetron_420::engine::rng::Rng;
+use memetron_420::hlt::collision::intersect_segment_circle;
+use memetron_420::hlt::constants::{FUDGE, MAX_SPEED, SHIP_RADIUS};
+use memetron_420::hlt::entity::{DockingStatus, Entity, GameState, Planet, Position, Ship};
+use memetron_420::hlt::game::Game;
+use memetron_420::hlt::game_map::GameMap;
+use memetron_420::hlt::player::Player;
+
+const SHIPS: usize = 300;
+const PLAYERS: usize = 4;
+const ROUNDS: usize = 20;
+
+fn crowded_state(width: f64, height: f64) -> GameState {
+    let mut rng = Rng::new(420);
+    let planets: Vec<Planet> = (0..12)
+        .map(|id| {
+            let position = Position(rng.range_f64(20.0, width - 20.0), rng.range_f64(20.0, height - 20.0));
+            Planet::new(id, position, 2000, rng.range_f64(3.0, 10.0), 4, 1000)
+        })
+        .collect();
+    let players = (0..PLAYERS)
+        .map(|player| {
+            let ships = (0..SHIPS / PLAYERS)
+                .map(|k| {
+                    let id = (player * SHIPS / PLAYERS + k) as i32;
+                    let position = Position(rng.range_f64(0.0, width), rng.range_f64(0.0, height));
+                    let mut ship = Ship::new(id, player as i32, position);
+                    if k % 4 == 0 {
+                        ship.docking_status = DockingStatus::DOCKED;
+                    }
+                    ship
+                })
+                .collect();
+            Player {
+                id: player as i32,
+                ships,
+                strength: 0.0,
+            }
+        })
+        .collect();
+    GameState { players, planets }
+}
+
+// the scans as they were before the index
+fn scan_enemies_in_range(ship: &Ship, game_map: &GameMap) -> usize {
+    game_map
+        .all_ships()
+        .iter()
+        .filter(|s| s.owner_id != ship.owner_id && s.in_attack_range_at(ship, 0.0))
+        .count()
+}
+
+fn scan_defenders<'a>(ship: &Ship, game_map: &'a GameMap) -> Vec<&'a Ship> {
+    game_map
+        .all_ships()
+        .into_iter()
+        .filter(|s| s.owner_id == ship.owner_id && s.is_undocked() && s.distance_to_less_than(ship, MAX_SPEED as f64))
+        .collect()
+}
+
+fn scan_damage_taken(ship: &Ship, game_map: &GameMap) -> i32 {
+    game_map
+        .all_ships()
+        .into_iter()
+        .filter(|s| s.is_undocked() && s.owner_id != ship.owner_id)
+        .map(|enemy| {
+            if enemy.in_attack_range_at(ship, 0.0) {
+                64 / scan_enemies_in_range(enemy, game_map)
+            } else if enemy.will_enter_attack_range(ship) {
+                64
+            } else {
+                0
+            }
+        })
+        .fold(0, |acc, s| acc + s as i32)
+}
+
+fn scan_closest_stationary(start: &Position, destination: &Position, game_map: &GameMap) -> Option<i32> {
+    let mut dist = 99999999f64;
+    let mut closest = None;
+    for planet in game_map.all_planets() {
+        let distance_to_surface = planet.distance_to(start) - (SHIP_RADIUS + planet.get_radius() + FUDGE);
+        if distance_to_surface < dist && intersect_segment_circle(start, destination, planet, FUDGE + SHIP_RADIUS) {
+            dist = distance_to_surface;
+            closest = Some(-planet.id - 1);
+        }
+    }
+    for other_ship in game_map.all_ships().iter().filter(|s| !s.is_undocked()) {
+        let distance_to_surface = other_ship.distance_to(start) - (SHIP_RADIUS + other_ship.get_radius() + FUDGE);
+        if distance_to_surface < dist && intersect_segment_circle(start, destination, *other_ship, FUDGE + SHIP_RADIUS) {
+            dist = distance_to_surface;
+            closest = Some(other_ship.id);
+        }
+    }
+    closest
+}
+
+fn time<F: FnMut() -> usize>(name: &str, mut f: F) -> f64 {
+    let start = Instant::now();
+    let mut checksum = 0;
+    for _ in 0..ROUNDS {
+        checksum += f();
+    }
+    let ms = start.elapsed().as_secs_f64() * 1000.0 / ROUNDS as f64;
+    println!("{:<40}{:>10.3}ms per round  (checksum {})", name, ms, checksum);
+    ms
+}
+
+fn compare<F: FnMut() -> usize, G: FnMut() -> usize>(name: &str, scan: F, indexed: G) {
+    let scan_ms = time(&format!("{} (scan)", name), scan);
+    let indexed_ms = time(&format!("{} (index)", name), indexed);
+    println!("{:<40}{:>10.1}x\n", "speedup", scan_ms / indexed_ms);
+}
+
+fn main() {
+    let game = Game::offline(0, 384, 256);
+    let state = crowded_state(384.0, 256.0);
+    time("build map and index", || GameMap::new(&game, state.clone()).state.players.len());
+    let game_map = GameMap::new(&game, state);
+    let ships = game_map.all_ships();
+    println!("{} ships, {} planets, {} rounds each\n", ships.len(), game_map.all_planets().len(), ROUNDS);
+
+    compare(
+        "enemies_in_attack_range_at",
+        || ships.iter().map(|s| scan_enemies_in_range(s, &game_map)).sum(),
+        || ships.iter().map(|s| s.enemies_in_attack_range_at(&game_map, 0.0)).sum(),
+    );
+    compare(
+        "defenders",
+        || ships.iter().map(|s| scan_defenders(s, &game_map).len()).sum(),
+        || ships.iter().map(|s| s.defenders(&game_map).len()).sum(),
+    );
+    compare(
+        "projected_damage_taken",
+        || ships.iter().map(|s| scan_damage_taken(s, &game_map) as usize).sum(),
+        || ships.iter().map(|s| s.projected_damage_taken(&game_map) as usize).sum(),
+    );
+    let destination = |s: &Ship| Position(s.get_position().0 + 7.0, s.get_position().1 + 3.0);
+    compare(
+        "closest_stationary_obstacle",
+        || {
+            ships
+                .iter()
+                .filter(|s| scan_closest_stationary(&s.get_position(), &destination(s), &game_map).is_some())
+                .count()
+        },
+        || {
+            ships
+                .iter()
+                .filter(|s| {
+                    game_map
+                        .closest_stationary_obstacle(&s.get_position(), &destination(s), FUDGE)
+                        .is_some()
+                })
+                .count()
+        },
+    );
+}
@@ -2,7 +2,8 @@
 set -e
 
 #cargo rustc --release -q -- -Awarnings
-cargo rustc --release -q -- -Awarnings -g -A dead_code
+cargo rustc --release -q --bin MyBot -- -Awarnings -g -A dead_code
+cargo build --release -q --bin halite
 
 [ -e log_0.txt ] && rm -f log_0.txt
 if ls *.hlt 1> /dev/null 2>&1; then
@@ -18,10 +19,10 @@ fi
 SIZE_Y=$(awk -v min=160 -v max=256 'BEGIN{srand(); print int(min+rand()*(max-min+1))}')
 SIZE_X=$((SIZE_Y * 3 / 2 ))
 
-#./halite_osx -t -s 476480283 -d "150 100"  "valgrind --tool=callgrind --log-file=lmao target/debug/MyBot" "bots/ipostv5"
-#./halite_osx -t -s 476480283 -d "210 140"  "valgrind --tool=callgrind --log-file=lmao target/debug/MyBot" "bots/ipostv5"
-#./halite_osx -t -s 476480285 -d "358 239"  "target/release/MyBot" "bots/ipostv6"
-./halite_osx -s 1479481806 -d "318 212"  "target/release/MyBot" "bots/ipostv6" "bots/ipostv6" "bots/ipostv6"
+#target/release/halite -t -s 476480283 -d "150 100"  "valgrind --tool=callgrind --log-file=lmao target/debug/MyBot" "bots/ipostv5"
+#target/release/halite -t -s 476480283 -d "210 140"  "valgrind --tool=callgrind --log-file=lmao target/debug/MyBot" "bots/ipostv5"
+#target/release/halite -t -s 476480285 -d "358 239"  "target/release/MyBot" "bots/ipostv6"
+target/release/halite -s 1479481806 -d "318 212"  "target/release/MyBot" "bots/ipostv6" "bots/ipostv6" "bots/ipostv6"
 
 rg "PT" log_0.txt | sort -r | head -n 15 >> turn_timings
 echo "" >> turn_timings
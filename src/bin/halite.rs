@@ -0,0 +1,100 @@
+extern crate memetron_420;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use memetron_420::engine::map::MAX_PLAYERS;
+use memetron_420::engine::runner::{parse_arg, run_match, MatchSettings};
+
+const USAGE: &str = "usage: halite [-t] [-s SEED] [-d \"WIDTH HEIGHT\"] [-i DIRECTORY] [-z] [--noreplay] BOT_COMMAND BOT_COMMAND [BOT_COMMAND...]
+
+  -t    disable bot timeouts
+  -s    map seed, random by default
+  -d    map dimensions, 240 160 by default
+  -i    directory replays are written to, replays by default
+  -z    zstd compress the replay, needs the zstd feature
+  --noreplay
+        do not write a replay
+
+Prints one tab separated line per player, best rank first.";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn parse_dimensions(dimensions: &str) -> (i32, i32) {
+    let parts: Vec<i32> = dimensions
+        .split_whitespace()
+        .map(|d| d.parse().unwrap_or_else(|_| usage()))
+        .collect();
+    match parts.as_slice() {
+        [width, height] if *width > 0 && *height > 0 => (*width, *height),
+        _ => usage(),
+    }
+}
+
+fn main() {
+    let mut settings = MatchSettings {
+        seed: None,
+        width: 240,
+        height: 160,
+        bots: vec![],
+        timeouts: true,
+        replay_directory: Some("replays".to_string()),
+        compress_replay: false,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-t" => settings.timeouts = false,
+            "-s" => settings.seed = Some(parse_arg(args.next(), usage)),
+            "-d" => {
+                let (width, height) = parse_dimensions(&args.next().unwrap_or_else(|| usage()));
+                settings.width = width;
+                settings.height = height;
+            }
+            "-i" => settings.replay_directory = Some(args.next().unwrap_or_else(|| usage())),
+            "-z" => settings.compress_replay = true,
+            "--noreplay" => settings.replay_directory = None,
+            "-h" | "--help" => usage(),
+            _ => settings.bots.push(arg),
+        }
+    }
+    if settings.bots.len() < 2 || settings.bots.len() > MAX_PLAYERS {
+        usage();
+    }
+
+    if let Some(ref directory) = settings.replay_directory {
+        if let Err(e) = fs::create_dir_all(directory) {
+            eprintln!("could not create replay directory {}: {}", directory, e);
+            process::exit(1);
+        }
+    }
+    let result = match run_match(&settings) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("could not start game: {}", e);
+            process::exit(1);
+        }
+    };
+    if let Some(ref replay) = result.replay {
+        eprintln!("replay written to {}", replay);
+    }
+    println!("# rank\tplayer\tname\tlast_turn_alive\tships\thp\tfailed\tseed\tcommand");
+    for player in result.by_rank() {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            player.rank,
+            player.id,
+            player.name,
+            player.last_turn_alive,
+            player.ships,
+            player.hp,
+            player.failed,
+            result.seed,
+            player.command
+        );
+    }
+}
@@ -0,0 +1,171 @@
+extern crate memetron_420;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use memetron_420::engine::ladder::{schedule, Ladder};
+use memetron_420::engine::runner::{clock_seed, parse_arg, run_matches, MatchSettings};
+use memetron_420::engine::tournament::Format;
+
+const USAGE: &str = "usage: ladder [-g GAMES] [-j THREADS] [-f 2|4|mixed] [-s SEED] [-r FILE] [-t] [-l] [BOT_COMMAND...]
+
+  -g    number of games to play, 50 by default
+  -j    games played at once, 2 by default
+  -f    2 or 4 player games or both, mixed by default
+  -s    schedule seed, random by default
+  -r    ratings file, ladder.txt by default
+  -t    disable bot timeouts
+  -l    only print the leaderboard
+
+Entrants default to every bot in bots/ plus target/release/MyBot, and are named after their executable.
+Ratings are saved after every game, so the ladder can be stopped and picked up again at any time.";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+// "RUST_BACKTRACE=1 bots/ipostv6" -> "ipostv6"
+fn entrant_name(command: &str) -> String {
+    let program = command
+        .split_whitespace()
+        .find(|w| !w.contains('='))
+        .unwrap_or(command);
+    Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| program.to_string())
+}
+
+fn default_entrants() -> Vec<String> {
+    let mut entrants: Vec<String> = fs::read_dir("bots")
+        .map(|dir| {
+            dir.filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| format!("bots/{}", entry.file_name().to_string_lossy()))
+                .collect()
+        })
+        .unwrap_or_default();
+    entrants.sort();
+    entrants.push("target/release/MyBot".to_string());
+    entrants
+}
+
+fn print_leaderboard(ladder: &Ladder) {
+    println!("# rank\tname\tscore\tmu\tsigma\tgames");
+    for (rank, (name, standing)) in ladder.leaderboard().into_iter().enumerate() {
+        println!(
+            "{}\t{}\t{:.2}\t{:.2}\t{:.2}\t{}",
+            rank + 1,
+            name,
+            standing.rating.conservative(),
+            standing.rating.mu,
+            standing.rating.sigma,
+            standing.games
+        );
+    }
+}
+
+fn main() {
+    let mut games = 50;
+    let mut threads = 2;
+    let mut format = Format::Mixed;
+    let mut seed = clock_seed();
+    let mut ratings_file = "ladder.txt".to_string();
+    let mut timeouts = true;
+    let mut leaderboard_only = false;
+    let mut entrants = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-g" => games = parse_arg(args.next(), usage),
+            "-j" => threads = parse_arg(args.next(), usage),
+            "-f" => {
+                format = match args.next().as_deref() {
+                    Some("2") => Format::TwoPlayer,
+                    Some("4") => Format::FourPlayer,
+                    Some("mixed") => Format::Mixed,
+                    _ => usage(),
+                }
+            }
+            "-s" => seed = parse_arg(args.next(), usage),
+            "-r" => ratings_file = args.next().unwrap_or_else(|| usage()),
+            "-t" => timeouts = false,
+            "-l" => leaderboard_only = true,
+            "-h" | "--help" => usage(),
+            _ => entrants.push(arg),
+        }
+    }
+
+    let ratings_path = Path::new(&ratings_file);
+    let mut ladder = match Ladder::load(ratings_path) {
+        Ok(ladder) => ladder,
+        Err(e) => {
+            eprintln!("could not read {}: {}", ratings_file, e);
+            process::exit(1);
+        }
+    };
+    if leaderboard_only {
+        print_leaderboard(&ladder);
+        return;
+    }
+
+    if entrants.is_empty() {
+        entrants = default_entrants();
+    }
+    let names: Vec<String> = entrants.iter().map(|e| entrant_name(e)).collect();
+    for (i, name) in names.iter().enumerate() {
+        if names.iter().skip(i + 1).any(|n| n == name) {
+            eprintln!("two entrants are called {}", name);
+            process::exit(2);
+        }
+    }
+    if entrants.len() < 2 {
+        usage();
+    }
+
+    let games = schedule(entrants.len(), games, format, seed);
+    let matches = games
+        .iter()
+        .map(|game| MatchSettings {
+            seed: Some(game.seed),
+            width: game.width,
+            height: game.height,
+            bots: game.entrants.iter().map(|&e| entrants[e].clone()).collect(),
+            timeouts,
+            replay_directory: None,
+            compress_replay: false,
+        })
+        .collect();
+
+    println!("# ladder seed {}", seed);
+    println!("# game\tseed\twidth\theight\tfinish order");
+    let result = run_matches(matches, threads, |index, result| {
+        let game = &games[index];
+        let finish_order: Vec<String> = result
+            .by_rank()
+            .iter()
+            .map(|p| names[game.entrants[p.id as usize]].clone())
+            .collect();
+        ladder.record(&finish_order);
+        if let Err(e) = ladder.save(ratings_path) {
+            eprintln!("could not save {}: {}", ratings_file, e);
+            return false;
+        }
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            index,
+            game.seed,
+            game.width,
+            game.height,
+            finish_order.join(", ")
+        );
+        true
+    });
+    if let Err(e) = result {
+        eprintln!("could not run game: {}", e);
+    }
+    print_leaderboard(&ladder);
+}
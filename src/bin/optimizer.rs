@@ -0,0 +1,129 @@
+extern crate memetron_420;
+
+use std::env;
+use std::path::Path;
+use std::process;
+
+use memetron_420::engine::optimizer::{default_keys, run_optimizer, Checkpoint, OptimizerSettings};
+use memetron_420::engine::runner::{bot_command, clock_seed, parse_arg};
+use memetron_420::engine::tournament::Format;
+use memetron_420::hlt::config::{Configs, KEYS};
+
+const USAGE: &str = "usage: optimizer [-p POPULATION] [-n GENERATIONS] [-g GAMES] [-j THREADS] [-f 2|4|mixed] [-s SEED] [-t]
+                 [-k KEY,KEY...] [-c CONFIG.json] [--checkpoint FILE] [-o FILE] BOT OPPONENT [OPPONENT...]
+
+  -p    individuals per generation, 12 by default
+  -n    generations, 20 by default
+  -g    games each individual plays per generation, 10 by default
+  -j    games played at once, 2 by default
+  -f    2 or 4 player games or both, 2 by default
+  -s    seed, random by default
+  -t    disable bot timeouts
+  -k    keys to tune, every key but the time limits by default
+  -c    config the search starts from, the defaults by default
+  --checkpoint
+        where progress is saved after every generation, optimizer_checkpoint.json by default. A run started with an
+        existing checkpoint carries on from it, keeping its seed and population, and must tune the same keys
+  -o    where the best config is written, best_config.json by default
+
+Searches the config space with a genetic algorithm. Each individual is played as BOT followed by its values as
+key=value arguments, e.g. \"target/release/MyBot\" or \"builtin:memetron\", against the opponents in rotation, and its
+fitness is the fraction of those games it won. Prints one tab separated line per generation.";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+fn main() {
+    let mut settings = OptimizerSettings {
+        bot: String::new(),
+        opponents: vec![],
+        keys: default_keys(),
+        start: Configs::default(),
+        population: 12,
+        generations: 20,
+        games: 10,
+        threads: 2,
+        format: Format::TwoPlayer,
+        seed: clock_seed(),
+        timeouts: true,
+    };
+    let mut checkpoint_path = "optimizer_checkpoint.json".to_string();
+    let mut output_path = "best_config.json".to_string();
+    let mut bots = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" => settings.population = parse_arg(args.next(), usage),
+            "-n" => settings.generations = parse_arg(args.next(), usage),
+            "-g" => settings.games = parse_arg(args.next(), usage),
+            "-j" => settings.threads = parse_arg(args.next(), usage),
+            "-f" => {
+                settings.format = match args.next().as_deref() {
+                    Some("2") => Format::TwoPlayer,
+                    Some("4") => Format::FourPlayer,
+                    Some("mixed") => Format::Mixed,
+                    _ => usage(),
+                }
+            }
+            "-s" => settings.seed = parse_arg(args.next(), usage),
+            "-t" => settings.timeouts = false,
+            "-k" => {
+                let keys: String = parse_arg(args.next(), usage);
+                settings.keys = keys.split(',').map(|k| k.trim().to_string()).collect();
+                if let Some(key) = settings.keys.iter().find(|k| !KEYS.contains(&k.as_str())) {
+                    fail(format!("unknown config key {:?}", key));
+                }
+            }
+            "-c" => {
+                let path: String = parse_arg(args.next(), usage);
+                settings.start = Configs::load(Path::new(&path)).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+            }
+            "--checkpoint" => checkpoint_path = parse_arg(args.next(), usage),
+            "-o" => output_path = parse_arg(args.next(), usage),
+            "-h" | "--help" => usage(),
+            _ => bots.push(arg),
+        }
+    }
+    if bots.len() < 2 || settings.threads == 0 || settings.population < 2 || settings.games == 0 {
+        usage();
+    }
+    settings.bot = bots.remove(0);
+    settings.opponents = bots.into_iter().map(bot_command).collect();
+
+    let checkpoint_path = Path::new(&checkpoint_path);
+    let checkpoint = match Checkpoint::load(checkpoint_path) {
+        Ok(Some(checkpoint)) => {
+            if let Err(e) = checkpoint.check(&settings) {
+                fail(format!("{}: {}", checkpoint_path.display(), e));
+            }
+            println!("# resuming from {} at generation {}", checkpoint_path.display(), checkpoint.generation);
+            checkpoint
+        }
+        Ok(None) => Checkpoint::new(&settings),
+        Err(e) => fail(format!("{}: {}", checkpoint_path.display(), e)),
+    };
+    println!("# optimizer seed {}, tuning {}", checkpoint.seed, settings.keys.join(", "));
+    println!("# generation\tbest\tmean\tbest ever");
+    let output_path = Path::new(&output_path);
+    let result = run_optimizer(&settings, checkpoint, checkpoint_path, |stats, checkpoint| {
+        let (ref best, best_fitness) = *checkpoint.best.as_ref().unwrap();
+        println!("{}\t{:.3}\t{:.3}\t{:.3}", stats.generation, stats.best, stats.mean, best_fitness);
+        if let Err(e) = best.save(output_path) {
+            fail(format!("could not write {}: {}", output_path.display(), e));
+        }
+    });
+    match result {
+        Ok(checkpoint) => if let Some((ref best, fitness)) = checkpoint.best {
+            println!("# best config, fitness {:.3}, written to {}", fitness, output_path.display());
+            print!("{}", best);
+        },
+        Err(e) => fail(format!("could not run game: {}", e)),
+    }
+}
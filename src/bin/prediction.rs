@@ -0,0 +1,64 @@
+extern crate memetron_420;
+
+use std::env;
+use std::path::Path;
+use std::process;
+
+use memetron_420::engine::replay::ReplayFile;
+use memetron_420::hlt::config::Configs;
+use memetron_420::hlt::game_map::GameMap;
+use memetron_420::strategy::prediction::{EnemyPrediction, Tally, PREDICTORS};
+
+const USAGE: &str = "usage: prediction REPLAY.hlt [REPLAY.hlt...]
+
+Plays back each replay from every player's point of view and scores how far off each enemy movement prediction
+model is, in map units per undocked enemy ship per turn, along with the model picked per ship by recent accuracy.
+Prints one tab separated line per replay and the totals over all of them.";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+// every player's prediction errors over the whole replay, added up
+fn replay_tally(replay: &ReplayFile) -> Result<Tally, String> {
+    let mut by_player: Vec<EnemyPrediction> = (0..replay.player_names.len())
+        .map(|_| EnemyPrediction::new(&Configs::default()))
+        .collect();
+    let games: Vec<_> = (0..by_player.len()).map(|my_id| replay.game(my_id)).collect();
+    for state in replay.frames() {
+        let state = state.map_err(|e| e.to_string())?;
+        for (my_id, prediction) in by_player.iter_mut().enumerate() {
+            let game_map = GameMap::new(&games[my_id], state.clone());
+            prediction.score(&game_map);
+            prediction.predict(&game_map.enemy_undocked_ships(), &game_map, &|| false);
+        }
+    }
+    let mut total = Tally::default();
+    for prediction in by_player.iter() {
+        total.add(&prediction.total());
+    }
+    Ok(total)
+}
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() || paths.iter().any(|p| p == "-h" || p == "--help") {
+        usage();
+    }
+    println!("# replay\tpredictions\t{}\tpicked", PREDICTORS.join("\t"));
+    let mut total = Tally::default();
+    for path in paths.iter() {
+        let replay = ReplayFile::load(Path::new(path)).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+        let tally = replay_tally(&replay).unwrap_or_else(|e| fail(format!("{}: {}", path, e)));
+        let means: Vec<String> = tally.means().iter().map(|e| format!("{:.3}", e)).collect();
+        println!("{}\t{}\t{}\t{:.3}", path, tally.picked_count(), means.join("\t"), tally.picked_mean());
+        total.add(&tally);
+    }
+    println!("# total: {}", total);
+}
@@ -0,0 +1,119 @@
+extern crate memetron_420;
+
+use std::env;
+use std::process;
+
+use memetron_420::engine::runner::{bot_command, clock_seed, parse_arg};
+use memetron_420::engine::tournament::{run_tournament, Format, TournamentSettings, WinRate, Z_95};
+
+const USAGE: &str = "usage: tournament [-g GAMES] [-j THREADS] [-f 2|4|mixed] [-s SEED] [-t] [--no-early-stop] CANDIDATE OPPONENT [OPPONENT...]
+
+  -g    number of games, 100 by default
+  -j    games played at once, 2 by default
+  -f    2 or 4 player games or both, 2 by default
+  -s    tournament seed, random by default
+  -t    disable bot timeouts
+  --no-early-stop
+        play every game even once the result is significant
+
+Bots are command lines, opponents which are just a name are looked up in bots/. builtin:memetron, builtin:settler,
+builtin:rush and builtin:attacker play the strategies built into the library without starting a process, and take
+key=value config overrides like MyBot does, e.g. \"builtin:memetron flee_threshold=2.5\".
+Prints one tab separated line per game as it finishes, then the win rate of the candidate.";
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn describe(name: &str, rate: &WinRate) {
+    if rate.games == 0 {
+        return;
+    }
+    let (low, high) = rate.interval(Z_95);
+    println!(
+        "# {}: won {} of {} games, {:.1}% (95% CI {:.1}% - {:.1}%, even would be {:.1}%)",
+        name,
+        rate.wins,
+        rate.games,
+        100.0 * rate.rate(),
+        100.0 * low,
+        100.0 * high,
+        100.0 * rate.baseline()
+    );
+}
+
+fn main() {
+    let mut settings = TournamentSettings {
+        candidate: String::new(),
+        opponents: vec![],
+        games: 100,
+        threads: 2,
+        format: Format::TwoPlayer,
+        seed: clock_seed(),
+        timeouts: true,
+        early_stop: true,
+    };
+    let mut bots = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-g" => settings.games = parse_arg(args.next(), usage),
+            "-j" => settings.threads = parse_arg(args.next(), usage),
+            "-f" => {
+                settings.format = match args.next().as_deref() {
+                    Some("2") => Format::TwoPlayer,
+                    Some("4") => Format::FourPlayer,
+                    Some("mixed") => Format::Mixed,
+                    _ => usage(),
+                }
+            }
+            "-s" => settings.seed = parse_arg(args.next(), usage),
+            "-t" => settings.timeouts = false,
+            "--no-early-stop" => settings.early_stop = false,
+            "-h" | "--help" => usage(),
+            _ => bots.push(arg),
+        }
+    }
+    if bots.len() < 2 || settings.threads == 0 {
+        usage();
+    }
+    settings.candidate = bots.remove(0);
+    settings.opponents = bots.into_iter().map(bot_command).collect();
+
+    println!("# tournament seed {}", settings.seed);
+    println!("# game\tseed\twidth\theight\tseat\trank\tfailed\tturns\twins\tplayed\tbots");
+    let summary = run_tournament(settings, |record, overall| {
+        let setup = &record.setup;
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            setup.index,
+            setup.seed,
+            setup.width,
+            setup.height,
+            setup.seat,
+            record.rank,
+            record.failed,
+            record.turns,
+            overall.wins,
+            overall.games,
+            setup.bots.join(", ")
+        );
+    });
+    let summary = match summary {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("could not run game: {}", e);
+            process::exit(1);
+        }
+    };
+
+    if summary.stopped_early {
+        println!("# stopped early, the result is significant");
+    }
+    describe("2 player", &summary.two_player);
+    describe("4 player", &summary.four_player);
+    describe("overall", &summary.overall);
+    let failures = summary.records.iter().filter(|r| r.failed).count();
+    println!("# {} failures", failures);
+}
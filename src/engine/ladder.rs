@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use engine::rng::Rng;
+use engine::tournament::{random_map_size, Format};
+use engine::trueskill::{rate, Rating};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Standing {
+    pub rating: Rating,
+    pub games: usize,
+}
+
+// TrueSkill ratings of every bot that has played on the ladder, keyed by name
+#[derive(Debug, Default)]
+pub struct Ladder {
+    pub standings: BTreeMap<String, Standing>,
+}
+
+impl Ladder {
+    // reads ratings saved by save, a missing file being an empty ladder
+    pub fn load(path: &Path) -> io::Result<Ladder> {
+        let mut ladder = Ladder::default();
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(ladder),
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let standing = match fields.as_slice() {
+                [_, mu, sigma, games] => match (mu.parse(), sigma.parse(), games.parse()) {
+                    (Ok(mu), Ok(sigma), Ok(games)) => Standing {
+                        rating: Rating { mu, sigma },
+                        games,
+                    },
+                    _ => return Err(bad_line(&line)),
+                },
+                _ => return Err(bad_line(&line)),
+            };
+            ladder.standings.insert(fields[0].to_string(), standing);
+        }
+        Ok(ladder)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "# name\tmu\tsigma\tgames")?;
+        for (name, standing) in self.standings.iter() {
+            writeln!(
+                file,
+                "{}\t{}\t{}\t{}",
+                name, standing.rating.mu, standing.rating.sigma, standing.games
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn standing(&self, name: &str) -> Standing {
+        self.standings.get(name).cloned().unwrap_or(Standing {
+            rating: Rating::new(),
+            games: 0,
+        })
+    }
+
+    // updates the ratings of everyone in a game, given from first place to last
+    pub fn record(&mut self, finish_order: &[String]) {
+        let ratings: Vec<Rating> = finish_order.iter().map(|name| self.standing(name).rating).collect();
+        for (name, rating) in finish_order.iter().zip(rate(&ratings)) {
+            let games = self.standing(name).games + 1;
+            self.standings.insert(name.clone(), Standing { rating, games });
+        }
+    }
+
+    // best first, by the conservative mu - 3 sigma estimate the official ladder also sorted by
+    pub fn leaderboard(&self) -> Vec<(&String, &Standing)> {
+        let mut standings: Vec<(&String, &Standing)> = self.standings.iter().collect();
+        standings.sort_by(|a, b| {
+            b.1
+                .rating
+                .conservative()
+                .partial_cmp(&a.1.rating.conservative())
+                .unwrap()
+        });
+        standings
+    }
+}
+
+fn bad_line(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad ladder line {:?}", line))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderGame {
+    pub seed: u32,
+    pub width: i32,
+    pub height: i32,
+    // indices into the entrant list, in seat order
+    pub entrants: Vec<usize>,
+}
+
+// Deals games out in rounds: every round shuffles the entrants and seats them at tables of 2 or 4, so everyone
+// plays about as often as everyone else and meets every opponent in every seat over time. A table left short at
+// the end of a round is filled up with entrants who already have a game that round. Mixed alternates 2 and 4
+// player rounds, and 4 player tables need at least 4 entrants
+pub fn schedule(num_entrants: usize, games: usize, format: Format, seed: u32) -> Vec<LadderGame> {
+    let mut rng = Rng::new(u64::from(seed));
+    let mut scheduled = vec![];
+    let mut round = 0;
+    while num_entrants >= 2 && scheduled.len() < games {
+        let four_player = match format {
+            Format::TwoPlayer => false,
+            Format::FourPlayer => true,
+            Format::Mixed => round % 2 == 1,
+        };
+        let table_size = if four_player && num_entrants >= 4 { 4 } else { 2 };
+        let mut order: Vec<usize> = (0..num_entrants).collect();
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.range(0, i as i32) as usize);
+        }
+        for table in order.chunks(table_size) {
+            let mut entrants = table.to_vec();
+            for &entrant in order.iter() {
+                if entrants.len() == table_size {
+                    break;
+                }
+                if !entrants.contains(&entrant) {
+                    entrants.push(entrant);
+                }
+            }
+            let (width, height) = random_map_size(&mut rng);
+            scheduled.push(LadderGame {
+                seed: rng.next_u64() as u32,
+                width,
+                height,
+                entrants,
+            });
+            if scheduled.len() == games {
+                break;
+            }
+        }
+        round += 1;
+    }
+    scheduled
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use engine::ladder::{schedule, Ladder};
+    use engine::tournament::Format;
+
+    #[test]
+    fn test_schedule() {
+        let games = schedule(5, 12, Format::Mixed, 1);
+        assert_eq!(12, games.len());
+        // first round: two 2 player tables and a third topped up, second round: a 4 player table and a short one
+        let sizes: Vec<usize> = games.iter().map(|g| g.entrants.len()).collect();
+        assert_eq!(vec![2, 2, 2, 4, 4, 2, 2, 2, 4, 4, 2, 2], sizes);
+        let mut played = [0; 5];
+        for game in games.iter() {
+            let mut entrants = game.entrants.clone();
+            entrants.sort();
+            entrants.dedup();
+            assert_eq!(game.entrants.len(), entrants.len());
+        }
+        for game in games.iter().take(3) {
+            for &e in game.entrants.iter() {
+                played[e] += 1;
+            }
+        }
+        assert!(played.iter().all(|&p| p >= 1));
+        assert_eq!(games, schedule(5, 12, Format::Mixed, 1));
+        assert_eq!(0, schedule(1, 12, Format::TwoPlayer, 1).len());
+    }
+
+    #[test]
+    fn test_ratings() {
+        let mut ladder = Ladder::default();
+        let names: Vec<String> = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        for _ in 0..10 {
+            ladder.record(&names);
+        }
+        let leaderboard: Vec<&String> = ladder.leaderboard().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(vec!["a", "b", "c"], leaderboard);
+        assert_eq!(10, ladder.standing("b").games);
+        assert_eq!(0, ladder.standing("d").games);
+
+        let path = env::temp_dir().join(format!("ladder_test_{}.txt", process::id()));
+        ladder.save(&path).unwrap();
+        let loaded = Ladder::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(ladder.standings, loaded.standings);
+    }
+}
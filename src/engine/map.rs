@@ -0,0 +1,203 @@
+use engine::rng::Rng;
+use hlt::constants::{BASE_SHIP_HEALTH, SHIP_COST};
+use hlt::entity::{Entity, GameState, Planet, Position, Ship};
+use hlt::player::Player;
+
+pub const MAX_PLAYERS: usize = 4;
+// every player starts with this many ships, spaced vertically around their starting point
+const STARTING_SHIPS: i32 = 3;
+const STARTING_SHIP_SPACING: f64 = 2.0;
+const MIN_PLANET_RADIUS: f64 = 3.0;
+const MAX_PLANET_RADIUS: f64 = 8.0;
+// free space kept between two planets and between a planet and the edge of the map
+const PLANET_SPACING: f64 = 4.0;
+// free space kept between a planet and a starting fleet
+const FLEET_CLEARANCE: f64 = 12.0;
+const PLACEMENT_ATTEMPTS: usize = 2000;
+
+// Planets are placed in groups which mirror each other so that every player sees the same layout from their
+// starting position: 2 player maps are point symmetric about the centre, 3 and 4 player maps are mirrored across
+// both axes. Only arithmetic which IEEE 754 rounds exactly is used, so a seed gives the same map on every machine
+pub fn generate(seed: u32, width: i32, height: i32, num_players: usize) -> GameState {
+    assert!(num_players > 0 && num_players <= MAX_PLAYERS);
+    let mut rng = Rng::new(u64::from(seed));
+    let (w, h) = (f64::from(width), f64::from(height));
+    let four_way = num_players > 2;
+
+    let anchors: Vec<Position> = if four_way {
+        vec![
+            Position(w * 0.25, h * 0.25),
+            Position(w * 0.75, h * 0.25),
+            Position(w * 0.25, h * 0.75),
+            Position(w * 0.75, h * 0.75),
+        ]
+    } else {
+        vec![Position(w * 0.25, h * 0.5), Position(w * 0.75, h * 0.5)]
+    };
+    let players = anchors
+        .iter()
+        .take(num_players)
+        .enumerate()
+        .map(|(id, anchor)| {
+            let ships = (0..STARTING_SHIPS)
+                .map(|i| {
+                    let offset = f64::from(i - STARTING_SHIPS / 2) * STARTING_SHIP_SPACING;
+                    Ship::new(id as i32 * STARTING_SHIPS + i, id as i32, Position(anchor.0, anchor.1 + offset))
+                })
+                .collect();
+            Player {
+                id: id as i32,
+                ships,
+                strength: 0.0,
+            }
+        })
+        .collect();
+
+    let mut layout = Layout {
+        width: w,
+        height: h,
+        four_way,
+        anchors,
+        planets: vec![],
+    };
+    // the middle of the map holds one large planet in 2 player games and a ring of four in 4 player games
+    if four_way {
+        layout.place(&mut rng, 1, |rng| {
+            let radius = rng.range_f64(MIN_PLANET_RADIUS, MAX_PLANET_RADIUS);
+            let offset = rng.range_f64(radius + PLANET_SPACING / 2.0, w.min(h) / 6.0);
+            (Position(w / 2.0 - offset, h / 2.0 - offset), radius)
+        });
+    } else {
+        layout.place(&mut rng, 1, |rng| {
+            (Position(w / 2.0, h / 2.0), rng.range_f64(5.0, MAX_PLANET_RADIUS))
+        });
+    }
+    let groups = if four_way { rng.range(4, 7) } else { rng.range(6, 10) };
+    let domain = if four_way { (w / 2.0, h / 2.0) } else { (w / 2.0, h) };
+    layout.place(&mut rng, groups, |rng| {
+        (
+            Position(rng.range_f64(0.0, domain.0), rng.range_f64(0.0, domain.1)),
+            rng.range_f64(MIN_PLANET_RADIUS, MAX_PLANET_RADIUS),
+        )
+    });
+
+    GameState {
+        players,
+        planets: layout.planets,
+    }
+}
+
+struct Layout {
+    width: f64,
+    height: f64,
+    four_way: bool,
+    anchors: Vec<Position>,
+    planets: Vec<Planet>,
+}
+
+impl Layout {
+    // draws candidate (position, radius) pairs until `groups` mirrored groups have been placed or the attempts run
+    // out
+    fn place<F>(&mut self, rng: &mut Rng, groups: i32, mut candidate: F)
+    where
+        F: FnMut(&mut Rng) -> (Position, f64),
+    {
+        let mut placed = 0;
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            if placed == groups {
+                break;
+            }
+            let (position, radius) = candidate(rng);
+            let group = self.mirror(position);
+            if self.fits(&group, radius) {
+                for position in group {
+                    let id = self.planets.len() as i32;
+                    self.planets.push(new_planet(id, position, radius));
+                }
+                placed += 1;
+            }
+        }
+    }
+
+    fn mirror(&self, Position(x, y): Position) -> Vec<Position> {
+        let (w, h) = (self.width, self.height);
+        let mut group = vec![Position(x, y)];
+        let images = if self.four_way {
+            vec![Position(w - x, y), Position(x, h - y), Position(w - x, h - y)]
+        } else {
+            vec![Position(w - x, h - y)]
+        };
+        // a planet on an axis of symmetry is its own mirror image
+        for image in images {
+            if group.iter().all(|p| *p != image) {
+                group.push(image);
+            }
+        }
+        group
+    }
+
+    fn fits(&self, group: &[Position], radius: f64) -> bool {
+        let margin = radius + PLANET_SPACING;
+        group.iter().enumerate().all(|(i, position)| {
+            position.0 >= margin && position.1 >= margin && position.0 <= self.width - margin
+                && position.1 <= self.height - margin
+                && group
+                    .iter()
+                    .skip(i + 1)
+                    .all(|other| !position.distance_to_less_than(other, 2.0 * radius + PLANET_SPACING))
+                && self.planets
+                    .iter()
+                    .all(|p| !position.distance_to_less_than(p, radius + p.radius + PLANET_SPACING))
+                && self.anchors
+                    .iter()
+                    .all(|a| !position.distance_to_less_than(a, radius + FLEET_CLEARANCE))
+        })
+    }
+}
+
+fn new_planet(id: i32, position: Position, radius: f64) -> Planet {
+    let docking_spots = ((radius * 0.75).round() as i32).clamp(2, 6);
+    let hp = (radius * f64::from(BASE_SHIP_HEALTH)).round() as i32;
+    let remaining_resources = (radius * f64::from(10 * SHIP_COST)).round() as i32;
+    Planet::new(id, position, hp, radius, docking_spots, remaining_resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::map::generate;
+    use hlt::entity::{Entity, Position};
+    use hlt::parse::Encodable;
+
+    #[test]
+    fn test_deterministic() {
+        let map = generate(1479481806, 318, 212, 4).to_wire();
+        assert_eq!(map, generate(1479481806, 318, 212, 4).to_wire());
+        assert!(map != generate(1479481807, 318, 212, 4).to_wire());
+    }
+
+    #[test]
+    fn test_layout() {
+        for seed in 0..50 {
+            for &(num_players, width, height) in [(2, 240, 160), (4, 384, 256), (4, 240, 160)].iter() {
+                let state = generate(seed, width, height, num_players);
+                let (w, h) = (f64::from(width), f64::from(height));
+                assert_eq!(num_players, state.players.len());
+                assert!(state.planets.len() >= 5);
+                for (i, planet) in state.planets.iter().enumerate() {
+                    assert!(planet.num_docking_spots >= 2 && planet.num_docking_spots <= 6);
+                    let Position(x, y) = planet.position;
+                    let mirrored = Position(w - x, h - y);
+                    assert!(state.planets.iter().any(|p| {
+                        p.radius == planet.radius && p.position.distance_to_less_than(&mirrored, 1e-9)
+                    }));
+                    for other in state.planets.iter().skip(i + 1) {
+                        assert!(planet.distance_to_surface(other) > 0.0);
+                    }
+                    for ship in state.players.iter().flat_map(|p| p.ships.iter()) {
+                        assert!(planet.distance_to_surface(ship) > 0.0);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,10 @@
+pub mod ladder;
+pub mod map;
+pub mod optimizer;
+pub mod replay;
+pub mod rng;
+pub mod runner;
+pub mod simulator;
+pub mod tournament;
+pub mod trueskill;
+pub mod wire;
@@ -0,0 +1,393 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use serde_json::{self, Value};
+
+use engine::rng::Rng;
+use engine::runner::{run_matches, MatchSettings};
+use engine::tournament::{game_setup, Format, TournamentSettings};
+use hlt::config::{Configs, KEYS};
+
+// the fittest of each generation carried over unchanged
+const ELITES: usize = 2;
+// parents are the fittest of this many individuals drawn at random
+const TOURNAMENT_SIZE: usize = 3;
+// chance of each value being mutated, and the spread of the mutation as a fraction of the value
+const MUTATION_RATE: f64 = 0.3;
+const MUTATION_SCALE: f64 = 0.2;
+
+pub struct OptimizerSettings {
+    // command line of the bot being tuned, which is given the tuned keys as key=value arguments, e.g.
+    // "target/release/MyBot" or "builtin:memetron"
+    pub bot: String,
+    // command lines of the bots it plays against, in rotation
+    pub opponents: Vec<String>,
+    // keys searched over, every other key keeps its value in start
+    pub keys: Vec<String>,
+    // the first individual, the rest of the first generation being mutations of it
+    pub start: Configs,
+    pub population: usize,
+    pub generations: usize,
+    // games each individual plays per generation
+    pub games: usize,
+    pub threads: usize,
+    pub format: Format,
+    // every generation's games are derived from this, so a run can be replayed
+    pub seed: u32,
+    pub timeouts: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best: f64,
+    pub mean: f64,
+}
+
+// everything needed to carry on a run: the population still to be evaluated and what was learned so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub seed: u32,
+    // the keys the population was bred over, which a run carrying on from the checkpoint has to tune too
+    pub keys: Vec<String>,
+    pub generation: usize,
+    pub population: Vec<Configs>,
+    // the fittest individual seen so far and its fitness
+    pub best: Option<(Configs, f64)>,
+    pub history: Vec<GenerationStats>,
+}
+
+impl Checkpoint {
+    pub fn new(settings: &OptimizerSettings) -> Checkpoint {
+        let mut rng = Rng::new(u64::from(settings.seed) << 32);
+        let mut population = vec![settings.start.clone()];
+        while population.len() < settings.population {
+            population.push(mutate(&settings.start, &settings.keys, &mut rng));
+        }
+        Checkpoint {
+            seed: settings.seed,
+            keys: settings.keys.clone(),
+            generation: 0,
+            population,
+            best: None,
+            history: vec![],
+        }
+    }
+
+    // reads a checkpoint written by save, a missing file being None
+    pub fn load(path: &Path) -> io::Result<Option<Checkpoint>> {
+        let mut text = String::new();
+        match File::open(path) {
+            Ok(mut file) => file.read_to_string(&mut text)?,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let json: Value = serde_json::from_str(&text).map_err(|e| bad_checkpoint(&e.to_string()))?;
+        Checkpoint::from_json(&json).map(Some)
+    }
+
+    // a run with the given settings can only carry on from this checkpoint if it tunes the same keys
+    pub fn check(&self, settings: &OptimizerSettings) -> io::Result<()> {
+        if self.keys == settings.keys {
+            Ok(())
+        } else {
+            Err(bad_checkpoint(&format!(
+                "it tunes {}, not {}",
+                self.keys.join(","),
+                settings.keys.join(",")
+            )))
+        }
+    }
+
+    fn from_json(json: &Value) -> io::Result<Checkpoint> {
+        let number = |v: &Value| v.as_f64().ok_or_else(|| bad_checkpoint(&format!("expected a number, got {}", v)));
+        let keys = match json["keys"].as_array() {
+            Some(keys) => keys
+                .iter()
+                .map(|k| {
+                    k.as_str()
+                        .map(|k| k.to_string())
+                        .ok_or_else(|| bad_checkpoint(&format!("expected a key, got {}", k)))
+                })
+                .collect::<io::Result<Vec<String>>>()?,
+            None => return Err(bad_checkpoint("no keys")),
+        };
+        let population = match json["population"].as_array() {
+            Some(population) => population.iter().map(Configs::from_json).collect::<io::Result<Vec<Configs>>>()?,
+            None => return Err(bad_checkpoint("no population")),
+        };
+        let best = match json["best"] {
+            Value::Null => None,
+            ref best => Some((Configs::from_json(&best["configs"])?, number(&best["fitness"])?)),
+        };
+        let history = match json["history"].as_array() {
+            Some(history) => history
+                .iter()
+                .map(|h| {
+                    Ok(GenerationStats {
+                        generation: number(&h["generation"])? as usize,
+                        best: number(&h["best"])?,
+                        mean: number(&h["mean"])?,
+                    })
+                })
+                .collect::<io::Result<Vec<GenerationStats>>>()?,
+            None => return Err(bad_checkpoint("no history")),
+        };
+        Ok(Checkpoint {
+            seed: number(&json["seed"])? as u32,
+            keys,
+            generation: number(&json["generation"])? as usize,
+            population,
+            best,
+            history,
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "seed": self.seed,
+            "keys": self.keys,
+            "generation": self.generation,
+            "population": self.population.iter().map(|c| c.to_json()).collect::<Vec<Value>>(),
+            "best": match self.best {
+                Some((ref configs, fitness)) => json!({"configs": configs.to_json(), "fitness": fitness}),
+                None => Value::Null,
+            },
+            "history": self.history
+                .iter()
+                .map(|h| json!({"generation": h.generation, "best": h.best, "mean": h.mean}))
+                .collect::<Vec<Value>>(),
+        })
+    }
+
+    // written to a temporary file first, so being interrupted part way leaves the previous checkpoint intact
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let temporary = path.with_extension("tmp");
+        {
+            let mut file = File::create(&temporary)?;
+            writeln!(file, "{}", serde_json::to_string_pretty(&self.to_json()).unwrap())?;
+        }
+        fs::rename(&temporary, path)
+    }
+}
+
+fn bad_checkpoint(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad checkpoint: {}", reason))
+}
+
+// the bot's command line with the individual's values of the tuned keys appended
+pub fn candidate_command(settings: &OptimizerSettings, configs: &Configs) -> String {
+    let mut command = settings.bot.clone();
+    for key in settings.keys.iter() {
+        command.push_str(&format!(" {}={}", key, configs.get(key).unwrap()));
+    }
+    command
+}
+
+// the tunable keys, which is everything but the time limits
+pub fn default_keys() -> Vec<String> {
+    KEYS.iter()
+        .filter(|k| !k.ends_with("_ms"))
+        .map(|k| k.to_string())
+        .collect()
+}
+
+// each value scaled by a log-normal factor with probability MUTATION_RATE, which keeps values positive and treats
+// small and large ones alike
+fn mutate(configs: &Configs, keys: &[String], rng: &mut Rng) -> Configs {
+    let mut mutated = configs.clone();
+    for key in keys.iter() {
+        if rng.next_f64() < MUTATION_RATE {
+            let value = configs.get(key).unwrap() * (MUTATION_SCALE * rng.gaussian()).exp();
+            mutated.set(key, value).unwrap();
+        }
+    }
+    mutated
+}
+
+// each value taken from a random point between the parents' values
+fn crossover(a: &Configs, b: &Configs, keys: &[String], rng: &mut Rng) -> Configs {
+    let mut child = a.clone();
+    for key in keys.iter() {
+        let (x, y) = (a.get(key).unwrap(), b.get(key).unwrap());
+        child.set(key, x + (y - x) * rng.next_f64()).unwrap();
+    }
+    child
+}
+
+fn select<'a>(ranked: &[&'a Configs], rng: &mut Rng) -> &'a Configs {
+    // ranked is fittest first, so the lowest index drawn wins
+    let drawn = (0..TOURNAMENT_SIZE)
+        .map(|_| rng.range(0, ranked.len() as i32 - 1) as usize)
+        .min()
+        .unwrap();
+    ranked[drawn]
+}
+
+// the next generation from this one's fitnesses: the elites as they are, and children of tournament selected
+// parents for the rest
+pub fn breed(population: &[Configs], fitness: &[f64], keys: &[String], rng: &mut Rng) -> Vec<Configs> {
+    let mut order: Vec<usize> = (0..population.len()).collect();
+    order.sort_by(|&a, &b| fitness[b].partial_cmp(&fitness[a]).unwrap());
+    let ranked: Vec<&Configs> = order.iter().map(|&i| &population[i]).collect();
+    let mut next: Vec<Configs> = ranked.iter().take(ELITES).map(|&c| c.clone()).collect();
+    while next.len() < population.len() {
+        let child = crossover(select(&ranked, rng), select(&ranked, rng), keys, rng);
+        next.push(mutate(&child, keys, rng));
+    }
+    next
+}
+
+// fraction of its games each individual won. Every individual of a generation plays the same maps and seats, and
+// the games of the whole generation are played as one batch so that every thread is kept busy
+fn evaluate(settings: &OptimizerSettings, population: &[Configs], generation_seed: u32) -> io::Result<Vec<f64>> {
+    let mut setups = vec![];
+    for (individual, configs) in population.iter().enumerate() {
+        let tournament = TournamentSettings {
+            candidate: candidate_command(settings, configs),
+            opponents: settings.opponents.clone(),
+            games: settings.games,
+            threads: settings.threads,
+            format: settings.format,
+            seed: generation_seed,
+            timeouts: settings.timeouts,
+            early_stop: false,
+        };
+        for index in 0..settings.games {
+            setups.push((individual, game_setup(&tournament, index)));
+        }
+    }
+    let matches = setups
+        .iter()
+        .map(|(_, setup)| MatchSettings {
+            seed: Some(setup.seed),
+            width: setup.width,
+            height: setup.height,
+            bots: setup.bots.clone(),
+            timeouts: settings.timeouts,
+            replay_directory: None,
+            compress_replay: false,
+        })
+        .collect();
+    let mut wins = vec![0; population.len()];
+    run_matches(matches, settings.threads, |index, result| {
+        let (individual, ref setup) = setups[index];
+        if result.players[setup.seat].rank == 1 {
+            wins[individual] += 1;
+        }
+        true
+    })?;
+    Ok(wins.iter().map(|&w| w as f64 / settings.games as f64).collect())
+}
+
+// Evolves the population from the checkpoint until settings.generations have been played, saving a checkpoint
+// after every generation and calling report with its stats. Elites are played again each generation rather than
+// keeping their fitness, so an individual that got lucky once doesn't stay on top
+pub fn run_optimizer<F>(settings: &OptimizerSettings, mut checkpoint: Checkpoint, path: &Path, mut report: F) -> io::Result<Checkpoint>
+where
+    F: FnMut(&GenerationStats, &Checkpoint),
+{
+    checkpoint.check(settings)?;
+    while checkpoint.generation < settings.generations {
+        let generation = checkpoint.generation;
+        let mut rng = Rng::new((u64::from(checkpoint.seed) << 32) | (generation as u64 + 1));
+        let generation_seed = rng.next_u64() as u32;
+        let fitness = evaluate(settings, &checkpoint.population, generation_seed)?;
+
+        let fittest = (0..fitness.len())
+            .max_by(|&a, &b| fitness[a].partial_cmp(&fitness[b]).unwrap())
+            .unwrap();
+        if checkpoint.best.as_ref().is_none_or(|&(_, best)| fitness[fittest] > best) {
+            checkpoint.best = Some((checkpoint.population[fittest].clone(), fitness[fittest]));
+        }
+        let stats = GenerationStats {
+            generation,
+            best: fitness[fittest],
+            mean: fitness.iter().sum::<f64>() / fitness.len() as f64,
+        };
+        checkpoint.history.push(stats.clone());
+        checkpoint.population = breed(&checkpoint.population, &fitness, &settings.keys, &mut rng);
+        checkpoint.generation += 1;
+        checkpoint.save(path)?;
+        report(&stats, &checkpoint);
+    }
+    Ok(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use engine::optimizer::{breed, candidate_command, default_keys, run_optimizer, Checkpoint, OptimizerSettings};
+    use engine::rng::Rng;
+    use engine::tournament::Format;
+    use hlt::config::Configs;
+
+    #[test]
+    fn test_breed() {
+        let keys = default_keys();
+        assert!(!keys.contains(&"time_cutoff_ms".to_string()));
+        let mut rng = Rng::new(1);
+        let mut fittest = Configs::default();
+        fittest.set("flee_threshold", 5.0).unwrap();
+        let population = vec![Configs::default(), fittest.clone(), Configs::default(), Configs::default()];
+        let next = breed(&population, &[0.1, 0.9, 0.5, 0.2], &keys, &mut rng);
+        assert_eq!(4, next.len());
+        assert_eq!(fittest, next[0]);
+        for configs in next.iter() {
+            for key in keys.iter() {
+                assert!(configs.get(key).unwrap() > 0.0);
+            }
+            // only tuned keys change
+            assert_eq!(Configs::default().time_cutoff_ms, configs.time_cutoff_ms);
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let path = env::temp_dir().join(format!("optimizer_test_{}.json", process::id()));
+        let mut settings = OptimizerSettings {
+            // settler ignores its configs, which keeps the games quick
+            bot: "builtin:settler".to_string(),
+            opponents: vec!["builtin:rush".to_string()],
+            keys: vec!["flee_threshold".to_string(), "edge_scale".to_string()],
+            start: Configs::default(),
+            population: 3,
+            generations: 1,
+            games: 1,
+            threads: 1,
+            format: Format::TwoPlayer,
+            seed: 7,
+            timeouts: false,
+        };
+        assert!(candidate_command(&settings, &settings.start).starts_with("builtin:settler flee_threshold=2 edge_scale="));
+
+        let first = run_optimizer(&settings, Checkpoint::new(&settings), &path, |_, _| {}).unwrap();
+        assert_eq!(1, first.history.len());
+        assert_eq!(3, first.population.len());
+        assert!(first.best.is_some());
+        let loaded = Checkpoint::load(&path).unwrap().unwrap();
+        assert_eq!(first, loaded);
+
+        // carrying on from the checkpoint only plays the generations left
+        settings.generations = 2;
+        let mut reported = vec![];
+        let second = run_optimizer(&settings, loaded, &path, |stats, _| reported.push(stats.generation)).unwrap();
+        assert_eq!(vec![1], reported);
+        assert_eq!(2, second.history.len());
+        assert_eq!(first.history[0], second.history[0]);
+
+        // but not when it would tune other keys than the population was bred over
+        settings.keys.pop();
+        settings.generations = 3;
+        let resumed = Checkpoint::load(&path).unwrap().unwrap();
+        assert!(resumed.check(&settings).is_err());
+        assert!(run_optimizer(&settings, resumed, &path, |_, _| panic!("played on")).is_err());
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(None, Checkpoint::load(&path).unwrap());
+    }
+}
@@ -0,0 +1,570 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use engine::simulator::{Event, Simulator};
+use hlt::command::Command;
+use hlt::constants::{BASE_PRODUCTIVITY, BASE_SHIP_HEALTH, DOCK_RADIUS, DOCK_TURNS, EXPLOSION_RADIUS, MAX_SHIP_HEALTH,
+                     MAX_SPEED, SHIP_RADIUS, SPAWN_RADIUS, WEAPON_COOLDOWN, WEAPON_DAMAGE, WEAPON_RADIUS};
+use hlt::entity::{DockingStatus, Entity, GameState, Planet, Position, Ship};
+use hlt::game::Game;
+use hlt::player::Player;
+
+// version of the official replay schema these files follow
+pub const REPLAY_VERSION: i32 = 31;
+// first bytes of a zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+// Records a game in the Halite II .hlt replay format so it can be opened in the online visualizer. Frame 0 is the
+// initial map, frame n the map after turn n together with the events of that turn, and moves[n - 1] the commands
+// which were issued for turn n
+pub struct Replay {
+    seed: u32,
+    width: i32,
+    height: i32,
+    planets: Vec<Value>,
+    frames: Vec<Value>,
+    moves: Vec<Value>,
+}
+
+impl Replay {
+    pub fn new(sim: &Simulator, seed: u32) -> Replay {
+        let planets = sim.state
+            .planets
+            .iter()
+            .map(|p| {
+                json!({
+                    "id": p.id,
+                    "x": p.position.0,
+                    "y": p.position.1,
+                    "r": p.radius,
+                    "health": p.hp,
+                    "docking_spots": p.num_docking_spots,
+                    "production": p.remaining_resources,
+                })
+            })
+            .collect();
+        Replay {
+            seed,
+            width: sim.width,
+            height: sim.height,
+            planets,
+            frames: vec![frame(&sim.state, &[])],
+            moves: vec![],
+        }
+    }
+
+    // records the commands issued for the turn the simulator has just stepped through, and its result
+    pub fn add_turn(&mut self, commands: &[Vec<Command>], sim: &Simulator) {
+        let mut players = Map::new();
+        for (player, player_commands) in commands.iter().enumerate() {
+            let mut moves = Map::new();
+            for command in player_commands {
+                if let Some((ship_id, command)) = encode_move(command) {
+                    moves.insert(ship_id.to_string(), command);
+                }
+            }
+            players.insert(player.to_string(), json!([moves]));
+        }
+        self.moves.push(Value::Object(players));
+        self.frames.push(frame(&sim.state, &sim.events));
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    // the whole replay; names are indexed by player id
+    pub fn to_json(&self, sim: &Simulator, names: &[String]) -> Value {
+        let rankings = sim.rankings();
+        let mut stats = Map::new();
+        for (i, player) in sim.state.players.iter().enumerate() {
+            stats.insert(
+                player.id.to_string(),
+                json!({
+                    "rank": 1 + rankings.iter().position(|id| *id == player.id).unwrap(),
+                    "last_frame_alive": sim.last_turn_alive(i),
+                }),
+            );
+        }
+        json!({
+            "version": REPLAY_VERSION,
+            "seed": self.seed,
+            "width": self.width,
+            "height": self.height,
+            "num_players": names.len(),
+            "num_frames": self.frames.len(),
+            "player_names": names,
+            "constants": constants(),
+            "poi": [],
+            "stats": stats,
+            "planets": self.planets,
+            "frames": self.frames,
+            "moves": self.moves,
+        })
+    }
+
+    // writes the replay to path, zstd compressed if asked to, like the official environment does
+    pub fn write(&self, path: &Path, sim: &Simulator, names: &[String], compress: bool) -> io::Result<()> {
+        let json = serde_json::to_vec(&self.to_json(sim, names))?;
+        let bytes = if compress { compress_bytes(&json)? } else { json };
+        File::create(path)?.write_all(&bytes)
+    }
+}
+
+// A replay read back from disk, either one written by Replay or one downloaded from the ladder. Frames are
+// rebuilt into the GameState the bot would have parsed on that turn
+pub struct ReplayFile {
+    pub seed: u32,
+    pub width: i32,
+    pub height: i32,
+    pub player_names: Vec<String>,
+    planets: Vec<Value>,
+    frames: Vec<Value>,
+}
+
+impl ReplayFile {
+    // reads a replay, compressed or not
+    pub fn load(path: &Path) -> io::Result<ReplayFile> {
+        let mut bytes = vec![];
+        File::open(path)?.read_to_end(&mut bytes)?;
+        if bytes.starts_with(&ZSTD_MAGIC) {
+            bytes = decompress_bytes(&bytes)?;
+        }
+        ReplayFile::from_json(serde_json::from_slice(&bytes)?)
+    }
+
+    pub fn from_json(mut json: Value) -> io::Result<ReplayFile> {
+        let player_names = field(&json, "player_names")?
+            .as_array()
+            .ok_or_else(|| invalid("player_names"))?
+            .iter()
+            .map(|name| name.as_str().map(|n| n.to_string()).ok_or_else(|| invalid("player_names")))
+            .collect::<io::Result<Vec<String>>>()?;
+        Ok(ReplayFile {
+            seed: as_i64(field(&json, "seed")?, "seed")? as u32,
+            width: as_i32(field(&json, "width")?, "width")?,
+            height: as_i32(field(&json, "height")?, "height")?,
+            player_names,
+            planets: take_array(&mut json, "planets")?,
+            frames: take_array(&mut json, "frames")?,
+        })
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    // the game as seen by the given player, for building a GameMap from any of the frames
+    pub fn game(&self, my_id: usize) -> Game {
+        Game::offline(my_id, self.width, self.height)
+    }
+
+    // every frame in order, with each ship's position history carried over the way Game::update_map does
+    pub fn frames(&self) -> Frames<'_> {
+        Frames {
+            replay: self,
+            index: 0,
+            previous: GameState {
+                players: vec![],
+                planets: vec![],
+            },
+        }
+    }
+
+    // a single frame, with the position history of every earlier frame
+    pub fn frame(&self, index: usize) -> io::Result<GameState> {
+        match self.frames().nth(index) {
+            Some(state) => state,
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no frame {}", index))),
+        }
+    }
+
+    // the frame on its own, ships only know their current position
+    fn decode_frame(&self, index: usize) -> io::Result<GameState> {
+        let frame = &self.frames[index];
+        let ships = field(frame, "ships")?;
+        let players = (0..self.player_names.len())
+            .map(|id| {
+                let mut ships = match ships.get(id.to_string()).and_then(|s| s.as_object()) {
+                    Some(fleet) => fleet.values().map(decode_ship).collect::<io::Result<Vec<Ship>>>()?,
+                    None => vec![],
+                };
+                // object keys are sorted as strings, the engine sends ships sorted by id
+                ships.sort_by_key(|s| s.id);
+                Ok(Player {
+                    id: id as i32,
+                    ships,
+                    strength: 0.0,
+                })
+            })
+            .collect::<io::Result<Vec<Player>>>()?;
+
+        let frame_planets = field(frame, "planets")?;
+        let mut planets = vec![];
+        for planet in self.planets.iter() {
+            let id = as_i32(field(planet, "id")?, "planet id")?;
+            // destroyed planets are left out of the frame
+            if let Some(current) = frame_planets.get(id.to_string()) {
+                planets.push(decode_planet(planet, current)?);
+            }
+        }
+        Ok(GameState { players, planets })
+    }
+}
+
+pub struct Frames<'a> {
+    replay: &'a ReplayFile,
+    index: usize,
+    previous: GameState,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = io::Result<GameState>;
+
+    fn next(&mut self) -> Option<io::Result<GameState>> {
+        if self.index >= self.replay.frames.len() {
+            return None;
+        }
+        let mut state = match self.replay.decode_frame(self.index) {
+            Ok(state) => state,
+            Err(e) => {
+                // later frames can't carry over the positions of this one, so that's the end of them
+                self.index = self.replay.frames.len();
+                return Some(Err(e));
+            }
+        };
+        self.index += 1;
+        Game::carry_over_positions(&self.previous, &mut state);
+        self.previous = state.clone();
+        Some(Ok(state))
+    }
+}
+
+fn decode_ship(json: &Value) -> io::Result<Ship> {
+    let position = Position(as_f64(field(json, "x")?, "ship x")?, as_f64(field(json, "y")?, "ship y")?);
+    let mut ship = Ship::new(
+        as_i32(field(json, "id")?, "ship id")?,
+        as_i32(field(json, "owner")?, "ship owner")?,
+        position,
+    );
+    ship.hp = as_i32(field(json, "health")?, "ship health")?;
+    ship.cooldown = as_i32(field(json, "cooldown")?, "ship cooldown")?;
+    ship.set_velocity(
+        as_f64(field(json, "vel_x")?, "ship vel_x")?,
+        as_f64(field(json, "vel_y")?, "ship vel_y")?,
+    );
+    let docking = field(json, "docking")?;
+    ship.docking_status = match field(docking, "status")?.as_str() {
+        Some("undocked") => DockingStatus::UNDOCKED,
+        Some("docking") => DockingStatus::DOCKING,
+        Some("docked") => DockingStatus::DOCKED,
+        Some("undocking") => DockingStatus::UNDOCKING,
+        _ => return Err(invalid("docking status")),
+    };
+    if ship.docking_status != DockingStatus::UNDOCKED {
+        ship.docked_planet = Some(as_i32(field(docking, "planet_id")?, "docked planet")?);
+        ship.progress = docking.get("turns_left").and_then(|t| t.as_i64()).unwrap_or(0) as i32;
+    }
+    Ok(ship)
+}
+
+// static planet properties come from the planet list at the top of the replay, the rest from the frame
+fn decode_planet(initial: &Value, current: &Value) -> io::Result<Planet> {
+    let mut planet = Planet::new(
+        as_i32(field(initial, "id")?, "planet id")?,
+        Position(as_f64(field(initial, "x")?, "planet x")?, as_f64(field(initial, "y")?, "planet y")?),
+        as_i32(field(current, "health")?, "planet health")?,
+        as_f64(field(initial, "r")?, "planet radius")?,
+        as_i32(field(initial, "docking_spots")?, "docking spots")?,
+        as_i32(field(current, "remaining_production")?, "remaining production")?,
+    );
+    planet.current_production = as_i32(field(current, "current_production")?, "current production")?;
+    planet.owner = match current.get("owner") {
+        Some(owner) if !owner.is_null() => Some(as_i32(owner, "planet owner")?),
+        _ => None,
+    };
+    planet.docked_ships = field(current, "docked_ships")?
+        .as_array()
+        .ok_or_else(|| invalid("docked_ships"))?
+        .iter()
+        .map(|id| as_i32(id, "docked ship"))
+        .collect::<io::Result<Vec<i32>>>()?;
+    Ok(planet)
+}
+
+fn invalid(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("bad or missing {} in replay", what))
+}
+
+fn field<'a>(json: &'a Value, key: &str) -> io::Result<&'a Value> {
+    json.get(key).ok_or_else(|| invalid(key))
+}
+
+fn take_array(json: &mut Value, key: &str) -> io::Result<Vec<Value>> {
+    match json.get_mut(key).map(|v| mem::replace(v, Value::Null)) {
+        Some(Value::Array(values)) => Ok(values),
+        _ => Err(invalid(key)),
+    }
+}
+
+fn as_i64(json: &Value, what: &str) -> io::Result<i64> {
+    json.as_i64().ok_or_else(|| invalid(what))
+}
+
+fn as_i32(json: &Value, what: &str) -> io::Result<i32> {
+    as_i64(json, what).map(|v| v as i32)
+}
+
+fn as_f64(json: &Value, what: &str) -> io::Result<f64> {
+    json.as_f64().ok_or_else(|| invalid(what))
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_bytes(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    zstd::decode_all(bytes)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_bytes(_: &[u8]) -> io::Result<Vec<u8>> {
+    Err(io::Error::other("compressed replays need the zstd feature"))
+}
+
+#[cfg(feature = "zstd")]
+fn compress_bytes(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    zstd::encode_all(bytes, 0)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compress_bytes(_: &[u8]) -> io::Result<Vec<u8>> {
+    Err(io::Error::other("compressed replays need the zstd feature"))
+}
+
+fn constants() -> Value {
+    json!({
+        "MAX_SPEED": MAX_SPEED,
+        "SHIP_RADIUS": SHIP_RADIUS,
+        "MAX_SHIP_HEALTH": MAX_SHIP_HEALTH,
+        "BASE_SHIP_HEALTH": BASE_SHIP_HEALTH,
+        "WEAPON_COOLDOWN": WEAPON_COOLDOWN,
+        "WEAPON_RADIUS": WEAPON_RADIUS,
+        "WEAPON_DAMAGE": WEAPON_DAMAGE,
+        "EXPLOSION_RADIUS": EXPLOSION_RADIUS,
+        "DOCK_RADIUS": DOCK_RADIUS,
+        "DOCK_TURNS": DOCK_TURNS,
+        "BASE_PRODUCTIVITY": BASE_PRODUCTIVITY,
+        "SPAWN_RADIUS": SPAWN_RADIUS,
+    })
+}
+
+fn encode_move(command: &Command) -> Option<(i32, Value)> {
+    match *command {
+        Command::Thrust(ship_id, magnitude, angle) => Some((
+            ship_id,
+            json!({"type": "thrust", "shipId": ship_id, "magnitude": magnitude, "angle": angle, "queue_number": 0}),
+        )),
+        Command::Dock(ship_id, planet_id) => Some((
+            ship_id,
+            json!({"type": "dock", "shipId": ship_id, "planet_id": planet_id, "queue_number": 0}),
+        )),
+        Command::Undock(ship_id) => Some((
+            ship_id,
+            json!({"type": "undock", "shipId": ship_id, "queue_number": 0}),
+        )),
+        Command::Stay() => None,
+    }
+}
+
+fn frame(state: &GameState, events: &[Event]) -> Value {
+    let mut ships = Map::new();
+    for player in state.players.iter() {
+        let mut fleet = Map::new();
+        for ship in player.ships.iter() {
+            let Position(x, y) = ship.get_position();
+            let docking = match ship.docking_status {
+                DockingStatus::UNDOCKED => json!({"status": "undocked"}),
+                status => json!({
+                    "status": docking_status_name(status),
+                    "planet_id": ship.docked_planet,
+                    "turns_left": ship.progress,
+                }),
+            };
+            fleet.insert(
+                ship.id.to_string(),
+                json!({
+                    "id": ship.id,
+                    "owner": ship.owner_id,
+                    "x": x,
+                    "y": y,
+                    "vel_x": ship.velocity_x.get(),
+                    "vel_y": ship.velocity_y.get(),
+                    "health": ship.hp,
+                    "cooldown": ship.cooldown,
+                    "docking": docking,
+                }),
+            );
+        }
+        ships.insert(player.id.to_string(), Value::Object(fleet));
+    }
+    let mut planets = Map::new();
+    for planet in state.planets.iter() {
+        planets.insert(
+            planet.id.to_string(),
+            json!({
+                "id": planet.id,
+                "health": planet.hp,
+                "docked_ships": planet.docked_ships,
+                "remaining_production": planet.remaining_resources,
+                "current_production": planet.current_production,
+                "owner": planet.owner,
+            }),
+        );
+    }
+    json!({
+        "ships": ships,
+        "planets": planets,
+        "events": events.iter().map(encode_event).collect::<Vec<Value>>(),
+    })
+}
+
+pub fn docking_status_name(status: DockingStatus) -> &'static str {
+    match status {
+        DockingStatus::UNDOCKED => "undocked",
+        DockingStatus::DOCKING => "docking",
+        DockingStatus::DOCKED => "docked",
+        DockingStatus::UNDOCKING => "undocking",
+    }
+}
+
+fn ship_entity((owner, id): (i32, i32)) -> Value {
+    json!({"type": "ship", "id": id, "owner": owner})
+}
+
+fn encode_event(event: &Event) -> Value {
+    match *event {
+        Event::Attack {
+            ship,
+            position,
+            time,
+            ref targets,
+        } => json!({
+            "event": "attack",
+            "entity": ship_entity(ship),
+            "x": position.0,
+            "y": position.1,
+            "time": time,
+            "targets": targets.iter().map(|t| ship_entity(t.0)).collect::<Vec<Value>>(),
+            "target_locations": targets.iter().map(|t| json!([(t.1).0, (t.1).1])).collect::<Vec<Value>>(),
+        }),
+        Event::ShipDestroyed { ship, position, time } => json!({
+            "event": "destroyed",
+            "entity": ship_entity(ship),
+            "x": position.0,
+            "y": position.1,
+            "radius": SHIP_RADIUS,
+            "time": time,
+        }),
+        Event::PlanetDestroyed {
+            planet_id,
+            position,
+            radius,
+            time,
+        } => json!({
+            "event": "destroyed",
+            "entity": {"type": "planet", "id": planet_id},
+            "x": position.0,
+            "y": position.1,
+            "radius": radius,
+            "time": time,
+        }),
+        Event::Spawned {
+            ship,
+            position,
+            planet_id,
+        } => json!({
+            "event": "spawned",
+            "entity": ship_entity(ship),
+            "x": position.0,
+            "y": position.1,
+            "planet": {"type": "planet", "id": planet_id, "owner": ship.0},
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::map::generate;
+    use engine::replay::{Replay, ReplayFile};
+    use engine::simulator::Simulator;
+    use hlt::command::Command;
+
+    #[test]
+    fn test_replay() {
+        let mut sim = Simulator::new(generate(7, 240, 160, 2), 240, 160);
+        let mut replay = Replay::new(&sim, 7);
+        let commands = vec![vec![Command::Thrust(0, 7, 0), Command::Stay()], vec![Command::Dock(3, 1)]];
+        sim.step(&commands);
+        replay.add_turn(&commands, &sim);
+
+        let names = vec!["a".to_string(), "b".to_string()];
+        let json = replay.to_json(&sim, &names);
+        assert_eq!(2, json["num_frames"]);
+        assert_eq!(json["planets"].as_array().unwrap().len(), sim.state.planets.len());
+        assert_eq!(7.0, json["frames"][1]["ships"]["0"]["0"]["x"].as_f64().unwrap() - 60.0);
+        assert_eq!("thrust", json["moves"][0]["0"][0]["0"]["type"]);
+        assert_eq!(1, json["moves"][0]["0"][0].as_object().unwrap().len());
+        assert_eq!(1, json["stats"]["0"]["rank"]);
+    }
+
+    #[test]
+    fn test_read_replay() {
+        let mut sim = Simulator::new(generate(7, 240, 160, 2), 240, 160);
+        let mut replay = Replay::new(&sim, 7);
+        for _ in 0..3 {
+            let commands = vec![vec![Command::Thrust(0, 7, 0)], vec![Command::Thrust(3, 7, 180)]];
+            sim.step(&commands);
+            replay.add_turn(&commands, &sim);
+        }
+        let names = vec!["a".to_string(), "b".to_string()];
+        let file = ReplayFile::from_json(replay.to_json(&sim, &names)).unwrap();
+        assert_eq!(4, file.num_frames());
+        assert_eq!(names, file.player_names);
+
+        let states: Vec<_> = file.frames().map(|f| f.unwrap()).collect();
+        let last = states.last().unwrap();
+        assert_eq!(sim.state.planets, last.planets);
+        for (player, simulated) in last.players.iter().zip(sim.state.players.iter()) {
+            for (ship, expected) in player.ships.iter().zip(simulated.ships.iter()) {
+                assert_eq!(expected.id, ship.id);
+                assert_eq!(expected.owner_id, ship.owner_id);
+                assert_eq!(expected.get_positions(), ship.get_positions());
+            }
+        }
+        assert_eq!(4, file.frame(3).unwrap().players[1].ships[0].get_positions().len());
+        assert!(file.frame(4).is_err());
+    }
+
+    #[test]
+    fn test_corrupt_frame() {
+        let mut sim = Simulator::new(generate(7, 240, 160, 2), 240, 160);
+        let mut replay = Replay::new(&sim, 7);
+        for _ in 0..3 {
+            sim.step(&[vec![], vec![]]);
+            replay.add_turn(&[vec![], vec![]], &sim);
+        }
+        let mut json = replay.to_json(&sim, &["a".to_string(), "b".to_string()]);
+        json["frames"][1]["ships"]["0"]["0"]["x"] = "x".into();
+        let file = ReplayFile::from_json(json).unwrap();
+
+        // the first error ends the frames
+        let frames: Vec<_> = file.frames().collect();
+        assert_eq!(2, frames.len());
+        assert!(frames[0].is_ok());
+        assert!(frames[1].is_err());
+        assert_eq!(1, file.frames().filter_map(Result::ok).count());
+        assert!(file.frame(3).is_err());
+    }
+}
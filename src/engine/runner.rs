@@ -0,0 +1,391 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command as Process, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use time;
+
+use engine::map;
+use engine::replay::Replay;
+use engine::simulator::Simulator;
+use engine::wire::parse_commands;
+use hlt::config::Configs;
+use hlt::game::Game;
+use hlt::logging::Logger;
+use hlt::parse::Encodable;
+use hlt::transport::Channels;
+use strategy::{by_name, play, STRATEGIES};
+
+// time allowed to read the initial map and reply with a name, and to reply to each turn
+const INIT_TIMEOUT_MS: u64 = 60000;
+const TURN_TIMEOUT_MS: u64 = 2000;
+// bot commands starting with this name one of the built in strategies, which is played on a thread of this process,
+// optionally followed by key=value config overrides, e.g. "builtin:memetron flee_threshold=2.5"
+pub const BUILTIN_PREFIX: &str = "builtin:";
+
+pub struct MatchSettings {
+    // map seed, picked from the clock when not given
+    pub seed: Option<u32>,
+    pub width: i32,
+    pub height: i32,
+    // command lines, e.g. "RUST_BACKTRACE=1 target/release/MyBot", or built in strategies, e.g. "builtin:settler"
+    pub bots: Vec<String>,
+    pub timeouts: bool,
+    // directory the replay is written to, no replay is kept when not given
+    pub replay_directory: Option<String>,
+    pub compress_replay: bool,
+}
+
+#[derive(Debug)]
+pub struct PlayerResult {
+    pub id: i32,
+    pub name: String,
+    pub command: String,
+    pub rank: usize,
+    pub last_turn_alive: i32,
+    pub ships: usize,
+    pub hp: i32,
+    // the bot crashed, timed out or closed its pipes before the game ended
+    pub failed: bool,
+}
+
+#[derive(Debug)]
+pub struct MatchResult {
+    pub seed: u32,
+    pub turns: i32,
+    // path of the replay file, if one was written
+    pub replay: Option<String>,
+    // ordered by player id
+    pub players: Vec<PlayerResult>,
+}
+
+impl MatchResult {
+    pub fn by_rank(&self) -> Vec<&PlayerResult> {
+        let mut players: Vec<&PlayerResult> = self.players.iter().collect();
+        players.sort_by_key(|p| p.rank);
+        players
+    }
+}
+
+// how lines get to a bot, its replies always come back over a channel
+enum Connection {
+    Process { child: Child, stdin: ChildStdin },
+    // dropping the sender ends the bot's input, after which it returns
+    Thread { input: Option<Sender<String>> },
+}
+
+struct Bot {
+    command: String,
+    name: String,
+    connection: Connection,
+    lines: Receiver<String>,
+    connected: bool,
+    failed: bool,
+}
+
+impl Bot {
+    fn spawn(command: &str) -> io::Result<Bot> {
+        match command.strip_prefix(BUILTIN_PREFIX) {
+            Some(name) => Bot::spawn_builtin(command, name),
+            None => Bot::spawn_process(command),
+        }
+    }
+
+    // runs the strategy exactly as MyBot would, minus the log files, talking over channels instead of pipes
+    fn spawn_builtin(command: &str, builtin: &str) -> io::Result<Bot> {
+        let mut words = builtin.split_whitespace();
+        let name = words.next().unwrap_or("");
+        if !STRATEGIES.contains(&name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no built in strategy {:?}", name)));
+        }
+        let mut configs = Configs::default();
+        for assignment in words {
+            configs.apply(assignment)?;
+        }
+        let (input, bot_input) = channel();
+        let (bot_output, lines) = channel();
+        let name = name.to_string();
+        thread::spawn(move || {
+            let transport = Channels {
+                input: bot_input,
+                output: bot_output,
+            };
+            let game = Game::with_transport(&name, Box::new(transport));
+            let mut strategy = by_name(&name, configs, Logger::discard()).unwrap();
+            play(&game, &mut *strategy, &mut Logger::discard());
+        });
+        Ok(Bot {
+            command: command.to_string(),
+            name: command.to_string(),
+            connection: Connection::Thread { input: Some(input) },
+            lines,
+            connected: true,
+            failed: false,
+        })
+    }
+
+    // commands are split on whitespace, with leading VAR=value words set in the bot's environment. The bot is
+    // started directly rather than through a shell so that killing it at the end of the game works
+    fn spawn_process(command: &str) -> io::Result<Bot> {
+        let mut words = command.split_whitespace().peekable();
+        let mut env = vec![];
+        while let Some(assignment) = words.next_if(|w| w.contains('=')) {
+            let mut parts = assignment.splitn(2, '=');
+            env.push((parts.next().unwrap(), parts.next().unwrap()));
+        }
+        let program = match words.next() {
+            Some(program) => program,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("empty bot command {:?}", command))),
+        };
+        let mut child = Process::new(program)
+            .args(words)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        // the bot's output is read on its own thread so that a hung bot can be timed out
+        let (sender, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => if sender.send(line).is_err() {
+                        break;
+                    },
+                    Err(_) => break,
+                }
+            }
+        });
+        Ok(Bot {
+            command: command.to_string(),
+            name: command.to_string(),
+            connection: Connection::Process { child, stdin },
+            lines,
+            connected: true,
+            failed: false,
+        })
+    }
+
+    fn send(&mut self, line: &str) {
+        if !self.connected {
+            return;
+        }
+        let sent = match self.connection {
+            Connection::Process { ref mut stdin, .. } => writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_ok(),
+            Connection::Thread { ref input } => input.as_ref().is_some_and(|i| i.send(line.to_string()).is_ok()),
+        };
+        if !sent {
+            self.disconnect();
+        }
+    }
+
+    fn receive(&mut self, deadline: Option<Instant>) -> Option<String> {
+        if !self.connected {
+            return None;
+        }
+        let line = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                let remaining = if deadline > now { deadline - now } else { Duration::from_millis(0) };
+                self.lines.recv_timeout(remaining).map_err(|e| e == RecvTimeoutError::Timeout)
+            }
+            None => self.lines.recv().map_err(|_| false),
+        };
+        match line {
+            Ok(line) => Some(line),
+            Err(_) => {
+                self.disconnect();
+                None
+            }
+        }
+    }
+
+    fn disconnect(&mut self) {
+        self.connected = false;
+        self.failed = true;
+        match self.connection {
+            Connection::Process { ref mut child, .. } => {
+                let _ = child.kill();
+            }
+            Connection::Thread { ref mut input } => *input = None,
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        if let Connection::Process { ref mut child, .. } = self.connection {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+fn deadline(settings: &MatchSettings, timeout_ms: u64) -> Option<Instant> {
+    if settings.timeouts {
+        Some(Instant::now() + Duration::from_millis(timeout_ms))
+    } else {
+        None
+    }
+}
+
+// "ipostv6" -> "bots/ipostv6", for the tools which take bots by name as well as by command line
+pub fn bot_command(bot: String) -> String {
+    if !bot.contains('/') && !bot.contains(' ') && Path::new("bots").join(&bot).exists() {
+        format!("bots/{}", bot)
+    } else {
+        bot
+    }
+}
+
+// the value of a tool's command line option, or its usage printed and the tool stopped if that's missing or
+// doesn't parse
+pub fn parse_arg<T: FromStr>(arg: Option<String>, usage: fn() -> !) -> T {
+    arg.and_then(|a| a.parse().ok()).unwrap_or_else(|| usage())
+}
+
+pub fn clock_seed() -> u32 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    (now.as_secs() as u32) ^ now.subsec_nanos()
+}
+
+// plays a full game between the given bots, speaking the same protocol as the official environment: the player
+// id, the map size and the initial map on startup, answered by the bot's name, then one map line per turn
+// answered by one command line
+pub fn run_match(settings: &MatchSettings) -> io::Result<MatchResult> {
+    let seed = settings.seed.unwrap_or_else(clock_seed);
+    let state = map::generate(seed, settings.width, settings.height, settings.bots.len());
+    let mut sim = Simulator::new(state, settings.width, settings.height);
+    let mut replay = Replay::new(&sim, seed);
+    let mut bots = settings
+        .bots
+        .iter()
+        .map(|command| Bot::spawn(command))
+        .collect::<io::Result<Vec<Bot>>>()?;
+
+    let initial_map = sim.state.to_wire();
+    for (id, bot) in bots.iter_mut().enumerate() {
+        bot.send(&id.to_string());
+        bot.send(&format!("{} {}", settings.width, settings.height));
+        bot.send(&initial_map);
+    }
+    let init_deadline = deadline(settings, INIT_TIMEOUT_MS);
+    for (id, bot) in bots.iter_mut().enumerate() {
+        match bot.receive(init_deadline) {
+            Some(name) => bot.name = name.trim().to_string(),
+            None => sim.eliminate(id as i32),
+        }
+    }
+
+    while !sim.is_over() {
+        let frame = sim.state.to_wire();
+        let alive = sim.alive_players();
+        for id in alive.iter() {
+            bots[*id as usize].send(&frame);
+        }
+        let turn_deadline = deadline(settings, TURN_TIMEOUT_MS);
+        let mut commands = vec![vec![]; bots.len()];
+        for id in alive {
+            match bots[id as usize].receive(turn_deadline) {
+                Some(line) => commands[id as usize] = parse_commands(&line),
+                None => sim.eliminate(id),
+            }
+        }
+        sim.step(&commands);
+        replay.add_turn(&commands, &sim);
+    }
+
+    let replay_path = match settings.replay_directory {
+        Some(ref directory) => {
+            let path = Path::new(directory).join(format!(
+                "replay-{}-{}-{}-{}.hlt",
+                time::now().strftime("%Y%m%d-%H%M%S").unwrap(),
+                seed,
+                settings.width,
+                settings.height
+            ));
+            let names: Vec<String> = bots.iter().map(|b| b.name.clone()).collect();
+            replay.write(&path, &sim, &names, settings.compress_replay)?;
+            Some(path.to_string_lossy().into_owned())
+        }
+        None => None,
+    };
+
+    let rankings = sim.rankings();
+    let players = bots.iter()
+        .enumerate()
+        .map(|(id, bot)| PlayerResult {
+            id: id as i32,
+            name: bot.name.clone(),
+            command: bot.command.clone(),
+            rank: 1 + rankings.iter().position(|r| *r == id as i32).unwrap(),
+            last_turn_alive: sim.last_turn_alive(id),
+            ships: sim.state.players[id].ships.len(),
+            hp: sim.fleet_health(id),
+            failed: bot.failed,
+        })
+        .collect();
+    Ok(MatchResult {
+        seed,
+        turns: sim.turn,
+        replay: replay_path,
+        players,
+    })
+}
+
+// plays the matches on a pool of threads, passing each result to on_result (along with the match's index) as it
+// finishes. Returning false from on_result stops any more matches being started; ones already in progress are
+// played out but not reported. The first error stops everything and is returned
+pub fn run_matches<F>(matches: Vec<MatchSettings>, threads: usize, mut on_result: F) -> io::Result<()>
+where
+    F: FnMut(usize, MatchResult) -> bool,
+{
+    let matches = Arc::new(matches);
+    let next_match = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (sender, results) = channel();
+    let workers: Vec<_> = (0..threads.max(1))
+        .map(|_| {
+            let (matches, next_match, stop, sender) = (matches.clone(), next_match.clone(), stop.clone(), sender.clone());
+            thread::spawn(move || loop {
+                let index = next_match.fetch_add(1, Ordering::SeqCst);
+                if index >= matches.len() || stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                if sender.send((index, run_match(&matches[index]))).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(sender);
+
+    let mut error = None;
+    for (index, result) in results {
+        if stop.load(Ordering::SeqCst) {
+            continue;
+        }
+        match result {
+            Ok(result) => if !on_result(index, result) {
+                stop.store(true, Ordering::SeqCst);
+            },
+            Err(e) => {
+                stop.store(true, Ordering::SeqCst);
+                error = Some(e);
+            }
+        }
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
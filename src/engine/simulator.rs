@@ -0,0 +1,649 @@
+use std::cmp::max;
+use std::collections::BTreeMap;
+
+use hlt::collision::{attack_radius, contact_time};
+use hlt::combat::{resolve_weapons, Combatant};
+use hlt::command::Command;
+use hlt::constants::{BASE_PRODUCTIVITY, DOCK_RADIUS, DOCK_TURNS, EXPLOSION_RADIUS, MAX_EXPLOSION_DAMAGE, MAX_SPEED,
+                     MIN_EXPLOSION_DAMAGE, SHIP_COST, SHIP_RADIUS, SPAWN_RADIUS, WEAPON_COOLDOWN};
+use hlt::entity::{DockingStatus, Entity, GameState, Position, Ship};
+
+// something that happens part way through the movement phase. Ships are referenced by their index into the
+// flattened ship list built at the start of the phase, planets by their index into GameState::planets
+#[derive(Debug, Clone, Copy)]
+enum Contact {
+    ShipShip(usize, usize),
+    ShipPlanet(usize, usize),
+    Attack,
+}
+
+// what happened to the game during a turn, as shown by the replay visualizer. Ships are referenced by (owner, id)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    // a ship fired at every enemy in range, at the given fraction of the turn
+    Attack {
+        ship: (i32, i32),
+        position: Position,
+        time: f64,
+        targets: Vec<((i32, i32), Position)>,
+    },
+    ShipDestroyed {
+        ship: (i32, i32),
+        position: Position,
+        time: f64,
+    },
+    PlanetDestroyed {
+        planet_id: i32,
+        position: Position,
+        radius: f64,
+        time: f64,
+    },
+    Spawned {
+        ship: (i32, i32),
+        position: Position,
+        planet_id: i32,
+    },
+}
+
+pub fn max_turns(width: i32, height: i32) -> i32 {
+    100 + f64::from(width * height).sqrt() as i32
+}
+
+// Rust implementation of the Halite II turn rules. Player ids are assumed to match their index in
+// GameState::players, which is also how the engine numbers them on the wire.
+pub struct Simulator {
+    pub state: GameState,
+    pub width: i32,
+    pub height: i32,
+    pub turn: i32,
+    // everything that happened during the last call to step
+    pub events: Vec<Event>,
+    next_ship_id: i32,
+    // last turn on which each player still had ships, indexed like GameState::players
+    last_turn_alive: Vec<i32>,
+}
+
+impl Simulator {
+    pub fn new(mut state: GameState, width: i32, height: i32) -> Simulator {
+        for player in state.players.iter_mut() {
+            for ship in player.ships.iter_mut() {
+                ship.owner_id = player.id;
+            }
+        }
+        let next_ship_id = state
+            .players
+            .iter()
+            .flat_map(|p| p.ships.iter())
+            .map(|s| s.id + 1)
+            .max()
+            .unwrap_or(0);
+        let last_turn_alive = vec![0; state.players.len()];
+        Simulator {
+            state,
+            width,
+            height,
+            turn: 0,
+            events: vec![],
+            next_ship_id,
+            last_turn_alive,
+        }
+    }
+
+    pub fn max_turns(&self) -> i32 {
+        max_turns(self.width, self.height)
+    }
+
+    pub fn alive_players(&self) -> Vec<i32> {
+        self.state
+            .players
+            .iter()
+            .filter(|p| !p.ships.is_empty())
+            .map(|p| p.id)
+            .collect()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.turn >= self.max_turns() || (self.state.players.len() > 1 && self.alive_players().len() <= 1)
+    }
+
+    // player ids from first to last place. Players who survive longer rank higher, ties are broken by the total
+    // health of their fleet
+    pub fn rankings(&self) -> Vec<i32> {
+        let mut indices: Vec<usize> = (0..self.state.players.len()).collect();
+        indices.sort_by(|&a, &b| {
+            self.last_turn_alive[b]
+                .cmp(&self.last_turn_alive[a])
+                .then(self.fleet_health(b).cmp(&self.fleet_health(a)))
+                .then(a.cmp(&b))
+        });
+        indices.into_iter().map(|i| self.state.players[i].id).collect()
+    }
+
+    pub fn last_turn_alive(&self, player_index: usize) -> i32 {
+        self.last_turn_alive[player_index]
+    }
+
+    pub fn fleet_health(&self, player_index: usize) -> i32 {
+        self.state.players[player_index].ships.iter().map(|s| s.hp).sum()
+    }
+
+    // removes a player from the game, e.g. because their bot crashed or timed out
+    pub fn eliminate(&mut self, player_id: i32) {
+        for player in self.state.players.iter_mut().filter(|p| p.id == player_id) {
+            for ship in player.ships.iter_mut() {
+                ship.hp = 0;
+                self.events.push(Event::ShipDestroyed {
+                    ship: (ship.owner_id, ship.id),
+                    position: ship.get_position(),
+                    time: 0.0,
+                });
+            }
+        }
+        self.remove_destroyed();
+    }
+
+    // advance the game by one turn, commands[i] being the commands issued by player i
+    pub fn step(&mut self, commands: &[Vec<Command>]) {
+        self.turn += 1;
+        self.events.clear();
+        self.apply_commands(commands);
+        self.resolve_movement();
+        self.process_docking();
+        self.process_production();
+        for ship in self.state.players.iter_mut().flat_map(|p| p.ships.iter_mut()) {
+            ship.reset_velocity();
+            ship.cooldown = max(0, ship.cooldown - 1);
+        }
+        for (i, player) in self.state.players.iter().enumerate() {
+            if !player.ships.is_empty() {
+                self.last_turn_alive[i] = self.turn;
+            }
+        }
+    }
+
+    fn apply_commands(&mut self, commands: &[Vec<Command>]) {
+        // planet id -> (player index, ship index) of every ship trying to dock there
+        let mut dock_attempts: BTreeMap<i32, Vec<(usize, usize)>> = BTreeMap::new();
+        for (player_index, player_commands) in commands.iter().enumerate().take(self.state.players.len()) {
+            for command in player_commands {
+                let ship_id = match *command {
+                    Command::Dock(ship_id, _) | Command::Undock(ship_id) | Command::Thrust(ship_id, _, _) => ship_id,
+                    Command::Stay() => continue,
+                };
+                let ships = &mut self.state.players[player_index].ships;
+                let ship_index = match ships.iter().position(|s| s.id == ship_id) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                let ship = &mut ships[ship_index];
+                match *command {
+                    Command::Thrust(_, magnitude, angle) => {
+                        if ship.is_undocked() && (0..=MAX_SPEED).contains(&magnitude) {
+                            let angle = f64::from(angle).to_radians();
+                            ship.set_velocity(f64::from(magnitude) * angle.cos(), f64::from(magnitude) * angle.sin());
+                        }
+                    }
+                    Command::Dock(_, planet_id) => {
+                        if ship.is_undocked() {
+                            dock_attempts
+                                .entry(planet_id)
+                                .or_default()
+                                .push((player_index, ship_index));
+                        }
+                    }
+                    Command::Undock(_) => {
+                        if ship.is_docked() {
+                            ship.docking_status = DockingStatus::UNDOCKING;
+                            ship.progress = DOCK_TURNS;
+                        }
+                    }
+                    Command::Stay() => {}
+                }
+            }
+        }
+        for (planet_id, attempts) in dock_attempts {
+            self.try_dock(planet_id, attempts);
+        }
+    }
+
+    fn try_dock(&mut self, planet_id: i32, attempts: Vec<(usize, usize)>) {
+        let planet_index = match self.state.planets.iter().position(|p| p.id == planet_id) {
+            Some(index) => index,
+            None => return,
+        };
+        let players = &mut self.state.players;
+        let planet = &mut self.state.planets[planet_index];
+        let valid: Vec<(usize, usize)> = attempts
+            .into_iter()
+            .filter(|&(p, s)| {
+                let ship = &players[p].ships[s];
+                ship.distance_to(planet) <= planet.radius + DOCK_RADIUS + SHIP_RADIUS
+                    && planet.owner.is_none_or(|owner| owner == ship.owner_id)
+            })
+            .collect();
+        // players racing for the same free planet on the same turn cancel each other out
+        if valid.iter().any(|&(p, _)| p != valid[0].0) {
+            return;
+        }
+        for (p, s) in valid {
+            if planet.docked_ships.len() as i32 >= planet.num_docking_spots {
+                break;
+            }
+            let ship = &mut players[p].ships[s];
+            ship.docking_status = DockingStatus::DOCKING;
+            ship.docked_planet = Some(planet.id);
+            ship.progress = DOCK_TURNS;
+            planet.docked_ships.push(ship.id);
+            planet.owner = Some(ship.owner_id);
+        }
+    }
+
+    // moves every ship along its thrust vector, resolving collisions, weapon fire and planet explosions in the
+    // order they happen during the turn
+    fn resolve_movement(&mut self) {
+        let ships: Vec<(usize, usize)> = self.state
+            .players
+            .iter()
+            .enumerate()
+            .flat_map(|(p, player)| (0..player.ships.len()).map(move |s| (p, s)))
+            .collect();
+
+        // ships already within range of each other fight at the very start of the turn
+        let mut contacts: Vec<(f64, Contact)> = vec![(0.0, Contact::Attack)];
+        for (i, &(p1, s1)) in ships.iter().enumerate() {
+            let ship = &self.state.players[p1].ships[s1];
+            for (j, &(p2, s2)) in ships.iter().enumerate().skip(i + 1) {
+                let other = &self.state.players[p2].ships[s2];
+                if let Some(t) = contact_time(ship, other, 2.0 * SHIP_RADIUS) {
+                    contacts.push((t, Contact::ShipShip(i, j)));
+                }
+                if ship.owner_id != other.owner_id && (ship.can_fire() || other.can_fire()) {
+                    if let Some(t) = contact_time(ship, other, attack_radius()) {
+                        contacts.push((t, Contact::Attack));
+                    }
+                }
+            }
+            for (k, planet) in self.state.planets.iter().enumerate() {
+                if let Some(t) = contact_time(ship, planet, planet.radius + SHIP_RADIUS) {
+                    contacts.push((t, Contact::ShipPlanet(i, k)));
+                }
+            }
+        }
+        contacts.sort_by(|c1, c2| c1.0.partial_cmp(&c2.0).unwrap());
+
+        let mut exploded = vec![false; self.state.planets.len()];
+        let mut destroyed = vec![false; ships.len()];
+        let mut index = 0;
+        while index < contacts.len() {
+            let time = contacts[index].0;
+            let mut attack = false;
+            while index < contacts.len() && contacts[index].0 == time {
+                match contacts[index].1 {
+                    Contact::ShipShip(i, j) => self.collide_ships(ships[i], ships[j]),
+                    Contact::ShipPlanet(i, k) => self.collide_planet(ships[i], k),
+                    Contact::Attack => attack = true,
+                }
+                index += 1;
+            }
+            if attack {
+                self.fire_weapons(&ships, time);
+            }
+            self.explode_planets(&ships, &mut exploded, time);
+            self.record_destroyed(&ships, &mut destroyed, time);
+        }
+
+        // survivors finish their thrust, anything which ends up off the map is lost
+        let (width, height) = (f64::from(self.width), f64::from(self.height));
+        for ship in self.state.players.iter_mut().flat_map(|p| p.ships.iter_mut()) {
+            if ship.hp <= 0 {
+                continue;
+            }
+            let end = ship.get_position_at(1.0);
+            if end.0 < 0.0 || end.1 < 0.0 || end.0 >= width || end.1 >= height {
+                ship.hp = 0;
+            } else {
+                ship.positions.push(end);
+            }
+        }
+        self.record_destroyed(&ships, &mut destroyed, 1.0);
+        self.remove_destroyed();
+    }
+
+    fn collide_ships(&mut self, (p1, s1): (usize, usize), (p2, s2): (usize, usize)) {
+        let hp1 = self.state.players[p1].ships[s1].hp;
+        let hp2 = self.state.players[p2].ships[s2].hp;
+        if hp1 <= 0 || hp2 <= 0 {
+            return;
+        }
+        self.state.players[p1].ships[s1].hp -= hp2;
+        self.state.players[p2].ships[s2].hp -= hp1;
+    }
+
+    fn collide_planet(&mut self, (p, s): (usize, usize), planet_index: usize) {
+        let ship = &mut self.state.players[p].ships[s];
+        let planet = &mut self.state.planets[planet_index];
+        if ship.hp <= 0 || planet.hp <= 0 {
+            return;
+        }
+        planet.hp -= ship.hp;
+        ship.hp = 0;
+    }
+
+    // every ship whose weapon is ready splits WEAPON_DAMAGE evenly between all enemies in range. Damage is
+    // applied simultaneously so ships destroyed at this instant still get their shots off
+    fn fire_weapons(&mut self, ships: &[(usize, usize)], t: f64) {
+        let (shots, damage) = {
+            let combatants: Vec<&Ship> = ships.iter().map(|&(p, s)| &self.state.players[p].ships[s]).collect();
+            resolve_weapons(&combatants, t)
+        };
+        for (i, targets) in shots {
+            let ship = |j: usize| &self.state.players[ships[j].0].ships[ships[j].1];
+            let attacker = ship(i);
+            let targets = targets
+                .into_iter()
+                .map(|j| ((ship(j).owner_id, ship(j).id), ship(j).get_position_at(t)))
+                .collect();
+            self.events.push(Event::Attack {
+                ship: (attacker.owner_id, attacker.id),
+                position: attacker.get_position_at(t),
+                time: t,
+                targets,
+            });
+            let (p, s) = ships[i];
+            self.state.players[p].ships[s].cooldown = WEAPON_COOLDOWN;
+        }
+        for (i, d) in damage.into_iter().enumerate() {
+            let (p, s) = ships[i];
+            self.state.players[p].ships[s].hp -= d;
+        }
+    }
+
+    // destroyed planets take their docked ships with them and damage everything near their surface
+    fn explode_planets(&mut self, ships: &[(usize, usize)], exploded: &mut [bool], t: f64) {
+        let players = &mut self.state.players;
+        for (k, planet) in self.state.planets.iter().enumerate() {
+            if planet.hp > 0 || exploded[k] {
+                continue;
+            }
+            exploded[k] = true;
+            for &(p, s) in ships {
+                let ship = &mut players[p].ships[s];
+                if ship.hp <= 0 {
+                    continue;
+                }
+                if ship.docked_planet == Some(planet.id) {
+                    ship.hp = 0;
+                    continue;
+                }
+                let distance = (ship.get_position_at(t).distance_to(&planet.position) - planet.radius).max(0.0);
+                if distance < EXPLOSION_RADIUS {
+                    ship.hp -= MIN_EXPLOSION_DAMAGE
+                        + ((1.0 - (distance / EXPLOSION_RADIUS))
+                            * f64::from(MAX_EXPLOSION_DAMAGE - MIN_EXPLOSION_DAMAGE)) as i32;
+                }
+            }
+        }
+    }
+
+    // adds an event for every ship and planet destroyed since the last call
+    fn record_destroyed(&mut self, ships: &[(usize, usize)], destroyed: &mut [bool], t: f64) {
+        for (i, &(p, s)) in ships.iter().enumerate() {
+            let ship = &self.state.players[p].ships[s];
+            if ship.hp <= 0 && !destroyed[i] {
+                destroyed[i] = true;
+                self.events.push(Event::ShipDestroyed {
+                    ship: (ship.owner_id, ship.id),
+                    position: ship.get_position_at(t),
+                    time: t,
+                });
+            }
+        }
+        for planet in self.state.planets.iter() {
+            let recorded = self.events.iter().any(|e| match *e {
+                Event::PlanetDestroyed { planet_id, .. } => planet_id == planet.id,
+                _ => false,
+            });
+            if planet.hp <= 0 && !recorded {
+                self.events.push(Event::PlanetDestroyed {
+                    planet_id: planet.id,
+                    position: planet.position,
+                    radius: planet.radius,
+                    time: t,
+                });
+            }
+        }
+    }
+
+    fn remove_destroyed(&mut self) {
+        let mut released = vec![];
+        for player in self.state.players.iter_mut() {
+            for ship in player.ships.iter().filter(|s| s.hp <= 0) {
+                if let Some(planet_id) = ship.docked_planet {
+                    released.push((planet_id, ship.id));
+                }
+            }
+            player.ships.retain(|s| s.hp > 0);
+        }
+        self.state.planets.retain(|p| p.hp > 0);
+        for (planet_id, ship_id) in released {
+            self.release(planet_id, ship_id);
+        }
+    }
+
+    fn release(&mut self, planet_id: i32, ship_id: i32) {
+        if let Some(planet) = self.state.planets.iter_mut().find(|p| p.id == planet_id) {
+            planet.docked_ships.retain(|id| *id != ship_id);
+            if planet.docked_ships.is_empty() {
+                planet.owner = None;
+                planet.current_production = 0;
+            }
+        }
+    }
+
+    fn process_docking(&mut self) {
+        let mut released = vec![];
+        for ship in self.state.players.iter_mut().flat_map(|p| p.ships.iter_mut()) {
+            match ship.docking_status {
+                DockingStatus::DOCKING => {
+                    ship.progress -= 1;
+                    if ship.progress <= 0 {
+                        ship.progress = 0;
+                        ship.docking_status = DockingStatus::DOCKED;
+                    }
+                }
+                DockingStatus::UNDOCKING => {
+                    ship.progress -= 1;
+                    if ship.progress <= 0 {
+                        ship.progress = 0;
+                        ship.docking_status = DockingStatus::UNDOCKED;
+                        if let Some(planet_id) = ship.docked_planet.take() {
+                            released.push((planet_id, ship.id));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (planet_id, ship_id) in released {
+            self.release(planet_id, ship_id);
+        }
+    }
+
+    // fully docked ships produce for their planet's owner. remaining_resources is left alone: planets never
+    // run dry on the ladder
+    fn process_production(&mut self) {
+        for k in 0..self.state.planets.len() {
+            let (planet_id, owner) = match self.state.planets[k].owner {
+                Some(owner) => (self.state.planets[k].id, owner),
+                None => continue,
+            };
+            let player_index = match self.state.players.iter().position(|p| p.id == owner) {
+                Some(index) => index,
+                None => continue,
+            };
+            let docked = self.state.players[player_index]
+                .ships
+                .iter()
+                .filter(|s| s.is_docked() && s.docked_planet == Some(planet_id))
+                .count() as i32;
+            self.state.planets[k].current_production += docked * BASE_PRODUCTIVITY;
+            while self.state.planets[k].current_production >= SHIP_COST {
+                match self.spawn_position(k) {
+                    Some(position) => {
+                        let ship = Ship::new(self.next_ship_id, owner, position);
+                        self.next_ship_id += 1;
+                        self.events.push(Event::Spawned {
+                            ship: (owner, ship.id),
+                            position,
+                            planet_id,
+                        });
+                        self.state.players[player_index].ships.push(ship);
+                        self.state.planets[k].current_production -= SHIP_COST;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    // new ships appear SPAWN_RADIUS off the planet's surface, preferring the side facing the centre of the map
+    // and sweeping around the planet until a free spot turns up
+    fn spawn_position(&self, planet_index: usize) -> Option<Position> {
+        let planet = &self.state.planets[planet_index];
+        let center = Position(f64::from(self.width) / 2.0, f64::from(self.height) / 2.0);
+        let base_angle = planet.calculate_angle_between(&center);
+        let distance = planet.radius + SPAWN_RADIUS;
+        for step in 0..36 {
+            let sign = if step % 2 == 0 { 1.0 } else { -1.0 };
+            let angle = (base_angle + sign * f64::from(((step + 1) / 2) * 10)).to_radians();
+            let position = Position(
+                planet.position.0 + distance * angle.cos(),
+                planet.position.1 + distance * angle.sin(),
+            );
+            if self.is_free(&position) {
+                return Some(position);
+            }
+        }
+        None
+    }
+
+    fn is_free(&self, position: &Position) -> bool {
+        position.0 >= SHIP_RADIUS && position.1 >= SHIP_RADIUS && position.0 < f64::from(self.width) - SHIP_RADIUS
+            && position.1 < f64::from(self.height) - SHIP_RADIUS
+            && self.state
+                .planets
+                .iter()
+                .all(|p| !p.distance_to_less_than(position, p.radius + SHIP_RADIUS))
+            && self.state
+                .players
+                .iter()
+                .flat_map(|p| p.ships.iter())
+                .all(|s| !s.distance_to_less_than(position, 2.0 * SHIP_RADIUS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::simulator::{Event, Simulator};
+    use hlt::command::Command;
+    use hlt::constants::{BASE_SHIP_HEALTH, DOCK_TURNS, WEAPON_DAMAGE};
+    use hlt::entity::{DockingStatus, Entity, GameState, Planet, Position, Ship};
+    use hlt::player::Player;
+
+    fn simulator(fleets: Vec<Vec<Position>>, planets: Vec<Planet>) -> Simulator {
+        let mut next_id = 0;
+        let players = fleets
+            .into_iter()
+            .enumerate()
+            .map(|(id, positions)| {
+                let ships = positions
+                    .into_iter()
+                    .map(|position| {
+                        next_id += 1;
+                        Ship::new(next_id, id as i32, position)
+                    })
+                    .collect();
+                Player {
+                    id: id as i32,
+                    ships,
+                    strength: 0.0,
+                }
+            })
+            .collect();
+        Simulator::new(GameState { players, planets }, 240, 160)
+    }
+
+    #[test]
+    fn test_thrust() {
+        let mut sim = simulator(vec![vec![Position(10.0, 10.0)]], vec![]);
+        sim.step(&[vec![Command::Thrust(1, 7, 90)]]);
+        let position = sim.state.players[0].ships[0].get_position();
+        assert!((position.0 - 10.0).abs() < 1e-9 && (position.1 - 17.0).abs() < 1e-9);
+        assert_eq!(2, sim.state.players[0].ships[0].positions.len());
+    }
+
+    #[test]
+    fn test_weapon_damage_is_split() {
+        let mut sim = simulator(
+            vec![
+                vec![Position(50.0, 50.0)],
+                vec![Position(53.0, 50.0), Position(50.0, 53.0)],
+            ],
+            vec![],
+        );
+        sim.step(&[vec![], vec![]]);
+        assert_eq!(BASE_SHIP_HEALTH - 2 * WEAPON_DAMAGE, sim.state.players[0].ships[0].hp);
+        assert_eq!(BASE_SHIP_HEALTH - WEAPON_DAMAGE / 2, sim.state.players[1].ships[0].hp);
+        assert_eq!(BASE_SHIP_HEALTH - WEAPON_DAMAGE / 2, sim.state.players[1].ships[1].hp);
+        assert_eq!(3, sim.events.iter().filter(|e| matches!(e, Event::Attack { .. })).count());
+    }
+
+    #[test]
+    fn test_planet_collision() {
+        let planet = Planet::new(0, Position(60.0, 50.0), 2000, 5.0, 3, 1000);
+        let mut sim = simulator(vec![vec![Position(50.0, 50.0)]], vec![planet]);
+        sim.step(&[vec![Command::Thrust(1, 7, 0)]]);
+        assert!(sim.state.players[0].ships.is_empty());
+        assert_eq!(2000 - BASE_SHIP_HEALTH, sim.state.planets[0].hp);
+    }
+
+    #[test]
+    fn test_leaving_map() {
+        let mut sim = simulator(vec![vec![Position(2.0, 50.0)], vec![Position(100.0, 100.0)]], vec![]);
+        sim.step(&[vec![Command::Thrust(1, 7, 180)], vec![]]);
+        assert!(sim.state.players[0].ships.is_empty());
+        assert!(sim.is_over());
+        assert_eq!(vec![1, 0], sim.rankings());
+    }
+
+    #[test]
+    fn test_dock_and_spawn() {
+        let planet = Planet::new(0, Position(60.0, 50.0), 2000, 5.0, 3, 1000);
+        let mut sim = simulator(vec![vec![Position(53.0, 50.0)]], vec![planet]);
+        sim.step(&[vec![Command::Dock(1, 0)]]);
+        assert_eq!(DockingStatus::DOCKING, sim.state.players[0].ships[0].docking_status);
+        assert_eq!(Some(0), sim.state.planets[0].owner);
+        for _ in 1..DOCK_TURNS {
+            sim.step(&[vec![]]);
+        }
+        assert!(sim.state.players[0].ships[0].is_docked());
+        // one docked ship needs SHIP_COST / BASE_PRODUCTIVITY = 12 turns to produce a ship
+        for _ in 0..10 {
+            sim.step(&[vec![]]);
+        }
+        assert_eq!(1, sim.state.players[0].ships.len());
+        sim.step(&[vec![]]);
+        assert_eq!(2, sim.state.players[0].ships.len());
+        assert!(matches!(sim.events[0], Event::Spawned { planet_id: 0, .. }));
+        assert!(sim.state.players[0].ships[1].distance_to_surface(&sim.state.planets[0]) > 0.0);
+    }
+
+    #[test]
+    fn test_contested_dock() {
+        let planet = Planet::new(0, Position(60.0, 50.0), 2000, 5.0, 3, 1000);
+        let mut sim = simulator(vec![vec![Position(53.0, 50.0)], vec![Position(67.0, 50.0)]], vec![planet]);
+        sim.step(&[vec![Command::Dock(1, 0)], vec![Command::Dock(2, 0)]]);
+        assert_eq!(None, sim.state.planets[0].owner);
+        assert!(sim.state.players[0].ships[0].is_undocked());
+    }
+}
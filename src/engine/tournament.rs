@@ -0,0 +1,268 @@
+use std::io;
+
+use engine::rng::Rng;
+use engine::runner::{run_matches, MatchSettings};
+
+// map heights on the ladder range from 160 to 256, widths are always 3/2 of the height
+const MIN_HEIGHT: i32 = 160;
+const MAX_HEIGHT: i32 = 256;
+// games played before the result is checked for significance
+const MIN_GAMES: usize = 10;
+// z score of the reported 95% confidence interval
+pub const Z_95: f64 = 1.96;
+// z score for stopping early. The result is checked after every game, so a plain 95% interval would stop on noise
+// far more often than one time in twenty
+const Z_STOP: f64 = 2.576;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    TwoPlayer,
+    FourPlayer,
+    // alternates between the two
+    Mixed,
+}
+
+pub struct TournamentSettings {
+    // command line of the bot under test
+    pub candidate: String,
+    // command lines of the bots it plays against, in rotation
+    pub opponents: Vec<String>,
+    pub games: usize,
+    pub threads: usize,
+    pub format: Format,
+    // every game's map is derived from this, so a tournament can be replayed
+    pub seed: u32,
+    pub timeouts: bool,
+    pub early_stop: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameSetup {
+    pub index: usize,
+    pub seed: u32,
+    pub width: i32,
+    pub height: i32,
+    // the candidate's player id
+    pub seat: usize,
+    pub bots: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    pub setup: GameSetup,
+    pub turns: i32,
+    // the candidate's rank and whether it crashed or timed out
+    pub rank: usize,
+    pub failed: bool,
+    // ranks of every player, indexed by player id
+    pub ranks: Vec<usize>,
+}
+
+impl GameRecord {
+    pub fn won(&self) -> bool {
+        self.rank == 1
+    }
+}
+
+// wins out of games played, next to the wins an evenly matched bot would expect: 1/2 of 2 player games and 1/4 of
+// 4 player games
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WinRate {
+    pub wins: usize,
+    pub games: usize,
+    expected_wins: f64,
+}
+
+impl WinRate {
+    pub fn add(&mut self, won: bool, players: usize) {
+        self.games += 1;
+        if won {
+            self.wins += 1;
+        }
+        self.expected_wins += 1.0 / players as f64;
+    }
+
+    pub fn rate(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.games as f64
+    }
+
+    pub fn baseline(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.expected_wins / self.games as f64
+    }
+
+    // Wilson score interval, which behaves far better than the normal approximation for small samples and win
+    // rates near 0 or 1
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        if self.games == 0 {
+            return (0.0, 1.0);
+        }
+        let n = self.games as f64;
+        let p = self.rate();
+        let denominator = 1.0 + z * z / n;
+        let center = (p + z * z / (2.0 * n)) / denominator;
+        let spread = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+        ((center - spread).max(0.0), (center + spread).min(1.0))
+    }
+
+    // the interval excludes the win rate of an evenly matched bot
+    pub fn significant(&self, z: f64) -> bool {
+        let (low, high) = self.interval(z);
+        self.games > 0 && (low > self.baseline() || high < self.baseline())
+    }
+}
+
+pub struct Summary {
+    // in the order the games finished
+    pub records: Vec<GameRecord>,
+    pub overall: WinRate,
+    pub two_player: WinRate,
+    pub four_player: WinRate,
+    pub stopped_early: bool,
+}
+
+// a map size in the range the ladder uses
+pub fn random_map_size(rng: &mut Rng) -> (i32, i32) {
+    let height = rng.range(MIN_HEIGHT, MAX_HEIGHT);
+    (height * 3 / 2, height)
+}
+
+// map, seat and opponents for one game. Each game gets its own generator so the schedule doesn't depend on the
+// order the threads pick games up in
+pub fn game_setup(settings: &TournamentSettings, index: usize) -> GameSetup {
+    let mut rng = Rng::new((u64::from(settings.seed) << 32) | index as u64);
+    let players = match settings.format {
+        Format::TwoPlayer => 2,
+        Format::FourPlayer => 4,
+        Format::Mixed => if index.is_multiple_of(2) {
+            2
+        } else {
+            4
+        },
+    };
+    let (width, height) = random_map_size(&mut rng);
+    let seed = rng.next_u64() as u32;
+    // the candidate takes every seat in turn, opponents are cycled through
+    let seat = index % players;
+    let mut bots: Vec<String> = (0..players - 1)
+        .map(|k| settings.opponents[(index * (players - 1) + k) % settings.opponents.len()].clone())
+        .collect();
+    bots.insert(seat, settings.candidate.clone());
+    GameSetup {
+        index,
+        seed,
+        width,
+        height,
+        seat,
+        bots,
+    }
+}
+
+fn match_settings(settings: &TournamentSettings, setup: &GameSetup) -> MatchSettings {
+    MatchSettings {
+        seed: Some(setup.seed),
+        width: setup.width,
+        height: setup.height,
+        bots: setup.bots.clone(),
+        timeouts: settings.timeouts,
+        replay_directory: None,
+        compress_replay: false,
+    }
+}
+
+// plays settings.games games on settings.threads threads, calling report as each one finishes
+pub fn run_tournament<F>(settings: TournamentSettings, mut report: F) -> io::Result<Summary>
+where
+    F: FnMut(&GameRecord, &WinRate),
+{
+    let setups: Vec<GameSetup> = (0..settings.games).map(|i| game_setup(&settings, i)).collect();
+    let matches = setups.iter().map(|s| match_settings(&settings, s)).collect();
+    let mut summary = Summary {
+        records: vec![],
+        overall: WinRate::default(),
+        two_player: WinRate::default(),
+        four_player: WinRate::default(),
+        stopped_early: false,
+    };
+    run_matches(matches, settings.threads, |index, result| {
+        let setup = setups[index].clone();
+        let candidate = &result.players[setup.seat];
+        let record = GameRecord {
+            turns: result.turns,
+            rank: candidate.rank,
+            failed: candidate.failed,
+            ranks: result.players.iter().map(|p| p.rank).collect(),
+            setup,
+        };
+        let players = record.setup.bots.len();
+        summary.overall.add(record.won(), players);
+        if players == 2 {
+            summary.two_player.add(record.won(), players);
+        } else {
+            summary.four_player.add(record.won(), players);
+        }
+        report(&record, &summary.overall);
+        summary.records.push(record);
+        if settings.early_stop && summary.overall.games >= MIN_GAMES && summary.overall.significant(Z_STOP) {
+            summary.stopped_early = summary.records.len() < settings.games;
+            return false;
+        }
+        true
+    })?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::tournament::{game_setup, Format, TournamentSettings, WinRate, Z_95};
+
+    #[test]
+    fn test_win_rate() {
+        let mut rate = WinRate::default();
+        for i in 0..20 {
+            rate.add(i < 10, 2);
+        }
+        let (low, high) = rate.interval(Z_95);
+        assert!((rate.baseline() - 0.5).abs() < 1e-9);
+        assert!(low < 0.5 && high > 0.5 && (0.5 - low - (high - 0.5)).abs() < 1e-9);
+        assert!(!rate.significant(Z_95));
+        for _ in 0..20 {
+            rate.add(true, 2);
+        }
+        assert!(rate.significant(Z_95));
+
+        // winning a third of 4 player games beats the 1/4 baseline given enough games
+        let mut rate = WinRate::default();
+        for i in 0..600 {
+            rate.add(i % 3 == 0, 4);
+        }
+        assert!(rate.significant(Z_95));
+    }
+
+    #[test]
+    fn test_game_setup() {
+        let settings = TournamentSettings {
+            candidate: "me".to_string(),
+            opponents: vec!["a".to_string(), "b".to_string()],
+            games: 8,
+            threads: 1,
+            format: Format::Mixed,
+            seed: 3,
+            timeouts: true,
+            early_stop: true,
+        };
+        for index in 0..8 {
+            let setup = game_setup(&settings, index);
+            assert_eq!(if index.is_multiple_of(2) { 2 } else { 4 }, setup.bots.len());
+            assert_eq!("me", setup.bots[setup.seat]);
+            assert!(setup.height >= 160 && setup.height <= 256 && setup.width == setup.height * 3 / 2);
+            assert_eq!(setup.seed, game_setup(&settings, index).seed);
+        }
+        assert!(game_setup(&settings, 0).seed != game_setup(&settings, 2).seed);
+    }
+}
@@ -0,0 +1,210 @@
+use std::f64::consts::PI;
+
+// the standard TrueSkill parameters, which the Halite ladder also used
+pub const MU: f64 = 25.0;
+pub const SIGMA: f64 = MU / 3.0;
+// spread of a single game's performance around a player's skill
+const BETA: f64 = SIGMA / 2.0;
+// uncertainty added before every game so ratings can keep moving
+const TAU: f64 = SIGMA / 100.0;
+const ITERATIONS: usize = 30;
+const CONVERGED: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub mu: f64,
+    pub sigma: f64,
+}
+
+impl Rating {
+    pub fn new() -> Rating {
+        Rating { mu: MU, sigma: SIGMA }
+    }
+
+    // the skill the player is 99% likely to have at least, used to order the leaderboard
+    pub fn conservative(&self) -> f64 {
+        self.mu - 3.0 * self.sigma
+    }
+}
+
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating::new()
+    }
+}
+
+// a gaussian in natural parameters, which makes multiplying and dividing messages cheap
+#[derive(Debug, Clone, Copy)]
+struct Gaussian {
+    pi: f64,
+    tau: f64,
+}
+
+impl Gaussian {
+    fn new(mean: f64, variance: f64) -> Gaussian {
+        Gaussian {
+            pi: 1.0 / variance,
+            tau: mean / variance,
+        }
+    }
+
+    fn uniform() -> Gaussian {
+        Gaussian { pi: 0.0, tau: 0.0 }
+    }
+
+    fn mean(&self) -> f64 {
+        if self.pi == 0.0 {
+            0.0
+        } else {
+            self.tau / self.pi
+        }
+    }
+
+    fn variance(&self) -> f64 {
+        1.0 / self.pi
+    }
+
+    fn mul(&self, other: &Gaussian) -> Gaussian {
+        Gaussian {
+            pi: self.pi + other.pi,
+            tau: self.tau + other.tau,
+        }
+    }
+
+    fn div(&self, other: &Gaussian) -> Gaussian {
+        Gaussian {
+            pi: self.pi - other.pi,
+            tau: self.tau - other.tau,
+        }
+    }
+}
+
+fn pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+fn cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / 2.0_f64.sqrt())
+}
+
+// complementary error function from Numerical Recipes, good to 1.2e-7 everywhere
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let r = t
+        * (-z * z - 1.265_512_23
+            + t * (1.000_023_68
+                + t * (0.374_091_96
+                    + t * (0.096_784_18
+                        + t * (-0.186_288_06
+                            + t * (0.278_868_07
+                                + t * (-1.135_203_98 + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+// Updates ratings after a free for all game, ratings being given from first place to last. Every player is their
+// own team and there are no draws, so the factor graph is a chain of performance differences between neighbouring
+// places, solved with expectation propagation
+pub fn rate(ratings: &[Rating]) -> Vec<Rating> {
+    let n = ratings.len();
+    if n < 2 {
+        return ratings.to_vec();
+    }
+    let skills: Vec<Gaussian> = ratings
+        .iter()
+        .map(|r| Gaussian::new(r.mu, r.sigma.powi(2) + TAU.powi(2)))
+        .collect();
+    let performances: Vec<Gaussian> = ratings
+        .iter()
+        .map(|r| Gaussian::new(r.mu, r.sigma.powi(2) + TAU.powi(2) + BETA.powi(2)))
+        .collect();
+
+    // messages from difference k to the performances of places k (above) and k + 1 (below), and from the win
+    // constraint on difference k back to it
+    let mut above = vec![Gaussian::uniform(); n - 1];
+    let mut below = vec![Gaussian::uniform(); n - 1];
+    let mut truncated = vec![Gaussian::uniform(); n - 1];
+    let marginal = |i: usize, above: &[Gaussian], below: &[Gaussian]| {
+        let mut m = performances[i];
+        if i < n - 1 {
+            m = m.mul(&above[i]);
+        }
+        if i > 0 {
+            m = m.mul(&below[i - 1]);
+        }
+        m
+    };
+
+    for _ in 0..ITERATIONS {
+        let mut delta: f64 = 0.0;
+        let order: Vec<usize> = (0..n - 1).chain((0..n - 1).rev()).collect();
+        for k in order {
+            let winner = marginal(k, &above, &below).div(&above[k]);
+            let loser = marginal(k + 1, &above, &below).div(&below[k]);
+            // the difference between the two performances, which has to come out positive
+            let difference = Gaussian::new(winner.mean() - loser.mean(), winner.variance() + loser.variance());
+            let (mean, variance) = (difference.mean(), difference.variance());
+            let t = mean / variance.sqrt();
+            let v = pdf(t) / cdf(t);
+            let w = v * (v + t);
+            let posterior = Gaussian::new(mean + variance.sqrt() * v, variance * (1.0 - w));
+            let message = posterior.div(&difference);
+            delta = delta.max((message.mean() - truncated[k].mean()).abs());
+            truncated[k] = message;
+
+            above[k] = Gaussian::new(message.mean() + loser.mean(), message.variance() + loser.variance());
+            below[k] = Gaussian::new(winner.mean() - message.mean(), winner.variance() + message.variance());
+        }
+        if delta < CONVERGED {
+            break;
+        }
+    }
+
+    // what the game says about each performance, passed back through the performance noise to the skill
+    (0..n)
+        .map(|i| {
+            let likelihood = marginal(i, &above, &below).div(&performances[i]);
+            let message = Gaussian::new(likelihood.mean(), likelihood.variance() + BETA.powi(2));
+            let skill = skills[i].mul(&message);
+            Rating {
+                mu: skill.mean(),
+                sigma: skill.variance().sqrt(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::trueskill::{rate, Rating};
+
+    #[test]
+    fn test_two_players() {
+        // values from the reference implementation with draws disabled
+        let rated = rate(&[Rating::new(), Rating::new()]);
+        assert!((rated[0].mu - 29.205).abs() < 0.001);
+        assert!((rated[0].sigma - 7.195).abs() < 0.001);
+        assert!((rated[1].mu - 20.795).abs() < 0.001);
+        assert!((rated[1].sigma - 7.195).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_four_players() {
+        let rated = rate(&[Rating::new(), Rating::new(), Rating::new(), Rating::new()]);
+        for i in 0..3 {
+            assert!(rated[i].mu > rated[i + 1].mu);
+            assert!(rated[i].sigma < Rating::new().sigma);
+            // equal players finishing in order come out symmetric about the prior
+            assert!((rated[i].mu + rated[3 - i].mu - 50.0).abs() < 1e-6);
+            assert!((rated[i].sigma - rated[3 - i].sigma).abs() < 1e-6);
+        }
+        // beating a much stronger player is worth more than beating an equal
+        let upset = rate(&[Rating::new(), Rating { mu: 35.0, sigma: 3.0 }]);
+        assert!(upset[0].mu > 29.205);
+    }
+}
@@ -0,0 +1,74 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use hlt::command::Command;
+
+// reads a bot's command line. Like the official environment this scans characters rather than whitespace
+// separated tokens, so "t 1 7 90t 2 7 90" is two thrusts. Parsing stops at the first malformed command
+pub fn parse_commands(line: &str) -> Vec<Command> {
+    let mut chars = line.chars().peekable();
+    let mut commands = vec![];
+    loop {
+        skip_whitespace(&mut chars);
+        let command = match chars.next() {
+            Some('t') => match (next_int(&mut chars), next_int(&mut chars), next_int(&mut chars)) {
+                (Some(ship), Some(magnitude), Some(angle)) => Command::Thrust(ship, magnitude, angle),
+                _ => break,
+            },
+            Some('d') => match (next_int(&mut chars), next_int(&mut chars)) {
+                (Some(ship), Some(planet)) => Command::Dock(ship, planet),
+                _ => break,
+            },
+            Some('u') => match next_int(&mut chars) {
+                Some(ship) => Command::Undock(ship),
+                None => break,
+            },
+            _ => break,
+        };
+        commands.push(command);
+    }
+    commands
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn next_int(chars: &mut Peekable<Chars>) -> Option<i32> {
+    skip_whitespace(chars);
+    let mut digits = String::new();
+    if chars.peek() == Some(&'-') {
+        digits.push('-');
+        chars.next();
+    }
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use engine::wire::parse_commands;
+    use hlt::command::Command;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            vec![Command::Thrust(1, 7, 90), Command::Dock(2, 3), Command::Undock(4)],
+            parse_commands("t 1 7 90 d 2 3 u 4\n")
+        );
+        assert_eq!(
+            vec![Command::Thrust(1, 7, 90), Command::Thrust(2, 7, 180)],
+            parse_commands("t 1 7 90t 2 7 180")
+        );
+        assert_eq!(vec![Command::Dock(2, 3)], parse_commands("d 2 3 t 1 x"));
+        assert!(parse_commands("").is_empty());
+    }
+}
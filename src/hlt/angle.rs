@@ -0,0 +1,98 @@
+use hlt::entity::Position;
+use hlt::vector::Vec2;
+
+// A direction in degrees from the x axis towards the y axis, the way the engine measures them, always kept in
+// [0, 360)
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Angle(f64);
+
+impl Angle {
+    pub fn degrees(degrees: f64) -> Angle {
+        let normalized = degrees.rem_euclid(360.0);
+        // rem_euclid rounds tiny negative angles up to exactly 360
+        Angle(if normalized >= 360.0 { 0.0 } else { normalized })
+    }
+
+    pub fn radians(radians: f64) -> Angle {
+        Angle::degrees(radians.to_degrees())
+    }
+
+    // the direction from one position to another
+    pub fn between(from: &Position, to: &Position) -> Angle {
+        (*to - *from).angle()
+    }
+
+    pub fn to_degrees(self) -> f64 {
+        self.0
+    }
+
+    pub fn to_radians(self) -> f64 {
+        self.0.to_radians()
+    }
+
+    // the shortest turn from this angle to the other, in [-180, 180) with positive being towards the y axis
+    pub fn difference(self, other: Angle) -> f64 {
+        Angle::degrees(other.0 - self.0 + 180.0).0 - 180.0
+    }
+
+    pub fn rotate(self, degrees: f64) -> Angle {
+        Angle::degrees(self.0 + degrees)
+    }
+
+    // the whole degrees in [0, 360) thrust commands take
+    pub fn to_engine(self) -> i32 {
+        self.0.round() as i32 % 360
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use hlt::angle::Angle;
+    use hlt::entity::Position;
+    use hlt::vector::Vec2;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn degrees() -> impl Strategy<Value = f64> {
+        -1080.0..1080.0
+    }
+
+    // how far apart two angles are either way around
+    fn apart(a1: Angle, a2: Angle) -> f64 {
+        a1.difference(a2).abs()
+    }
+
+    proptest! {
+        #[test]
+        fn test_angle_normalized(degrees in degrees()) {
+            let angle = Angle::degrees(degrees);
+            prop_assert!(angle.to_degrees() >= 0.0 && angle.to_degrees() < 360.0);
+            let turns = (degrees - angle.to_degrees()) / 360.0;
+            prop_assert!((turns - turns.round()).abs() < EPSILON);
+            prop_assert!(apart(angle, Angle::radians(degrees.to_radians())) < EPSILON);
+            let engine = angle.to_engine();
+            prop_assert!((0..360).contains(&engine));
+            prop_assert!(apart(angle, Angle::degrees(f64::from(engine))) <= 0.5 + EPSILON);
+        }
+
+        #[test]
+        fn test_angle_difference_and_rotation(degrees in degrees(), turn in -180.0..180.0f64) {
+            let angle = Angle::degrees(degrees);
+            let rotated = angle.rotate(turn);
+            prop_assert!((angle.difference(rotated) - turn).abs() < EPSILON);
+            prop_assert!((rotated.difference(angle) + turn).abs() < EPSILON || (turn + 180.0).abs() < EPSILON);
+            prop_assert!(apart(angle, angle.rotate(turn + 360.0).rotate(-turn)) < EPSILON);
+        }
+
+        #[test]
+        fn test_angle_between(degrees in degrees(), distance in 1.0..100.0f64) {
+            let from = Position(120.0, 80.0);
+            let angle = Angle::degrees(degrees);
+            let to = from + Position::from_polar(distance, angle);
+            prop_assert!(apart(angle, Angle::between(&from, &to)) < EPSILON);
+            prop_assert!(apart(angle.rotate(180.0), Angle::between(&to, &from)) < EPSILON);
+        }
+    }
+}
@@ -1,5 +1,10 @@
 
-use hlt::entity::{Entity, Position};
+use hlt::constants::{SHIP_RADIUS, WEAPON_RADIUS};
+use hlt::entity::Entity;
+use hlt::vector::Vec2;
+
+// contact times are rounded to this many steps per turn so that nearly simultaneous events are resolved together
+const TIME_PRECISION: f64 = 10000.0;
 
 /*
    Test whether a line segment and circle intersect.
@@ -12,33 +17,55 @@ use hlt::entity::{Entity, Position};
    :rtype: bool
    */
 pub fn intersect_segment_circle<E: Entity, F: Entity, G: Entity>(start: &E, end: &F, circle: &G, fudge: f64) -> bool {
-    let Position(start_x, start_y) = start.get_position();
-    let Position(end_x, end_y) = end.get_position();
-    let Position(circle_x, circle_y) = circle.get_position();
-    let dx = end_x - start_x;
-    let dy = end_y - start_y;
-
-    let a = dx.powi(2) + dy.powi(2);
-    let b = -2.0
-        * (start_x.powi(2) - start_x * end_x - start_x * circle_x + end_x * circle_x + start_y.powi(2) - start_y * end_y
-            - start_y * circle_y + end_y * circle_y);
-
-    if a == 0.0 {
+    let start_position = start.get_position();
+    let segment = end.get_position() - start_position;
+    let to_circle = circle.get_position() - start_position;
+
+    if segment.length_squared() == 0.0 {
         // Start and end are the same point.
         return start.distance_to(circle) <= circle.get_radius() + fudge;
     }
 
-    let &t = [-b / (2.0 * a), 1.0]
-        .iter()
-        .min_by(|x, y| x.partial_cmp(y).unwrap())
-        .unwrap();
+    // how far along the segment the point closest to the circle is
+    let t = (segment.dot(to_circle) / segment.length_squared()).min(1.0);
     if t < 0.0 {
         return false;
     }
 
-    let closest_x = start_x + dx * t;
-    let closest_y = start_y + dy * t;
-    let closest_distance = Position(closest_x, closest_y).distance_to(circle);
+    let closest_distance = (start_position + segment * t).distance_to(circle);
+
+    closest_distance <= circle.get_radius() + fudge
+}
+
+// weapons reach WEAPON_RADIUS beyond the edge of the attacking ship to the edge of its target
+pub fn attack_radius() -> f64 {
+    WEAPON_RADIUS + 2.0 * SHIP_RADIUS
+}
+
+// earliest time in [0, 1] at which the two entities, moving at their current velocities, come within radius of
+// each other
+pub fn contact_time<A: Entity, B: Entity>(a: &A, b: &B, radius: f64) -> Option<f64> {
+    let offset = a.get_position() - b.get_position();
+    let relative_velocity = (a.get_position_at(1.0) - a.get_position()) - (b.get_position_at(1.0) - b.get_position());
+
+    let qa = relative_velocity.length_squared();
+    let qb = 2.0 * offset.dot(relative_velocity);
+    let qc = offset.length_squared() - radius.powi(2);
 
-    return closest_distance <= circle.get_radius() + fudge;
+    if qc <= 0.0 {
+        return Some(0.0);
+    }
+    if qa == 0.0 {
+        return None;
+    }
+    let discriminant = qb.powi(2) - 4.0 * qa * qc;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-qb - discriminant.sqrt()) / (2.0 * qa);
+    if (0.0..=1.0).contains(&t) {
+        Some((t * TIME_PRECISION).round() / TIME_PRECISION)
+    } else {
+        None
+    }
 }
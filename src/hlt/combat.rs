@@ -0,0 +1,439 @@
+use std::cmp::max;
+
+use hlt::collision::{attack_radius, contact_time};
+use hlt::constants::{MAX_SPEED, SHIP_RADIUS, WEAPON_COOLDOWN, WEAPON_DAMAGE};
+use hlt::entity::{Entity, Position, Ship};
+use hlt::game_map::GameMap;
+
+// a ship as far as a fight is concerned
+#[derive(Debug, Clone)]
+pub struct Fighter {
+    pub id: i32,
+    pub owner_id: i32,
+    pub position: Position,
+    pub velocity: (f64, f64),
+    pub hp: i32,
+    pub cooldown: i32,
+    // docked, docking and undocking ships can be shot but don't fire back
+    pub docked: bool,
+}
+
+impl Fighter {
+    pub fn new(ship: &Ship) -> Fighter {
+        Fighter {
+            id: ship.id,
+            owner_id: ship.owner_id,
+            position: ship.get_position(),
+            velocity: (ship.velocity_x.get(), ship.velocity_y.get()),
+            hp: ship.hp,
+            cooldown: ship.cooldown,
+            docked: !ship.is_undocked(),
+        }
+    }
+
+}
+
+// what resolving weapon fire needs to know about a ship, whether the engine's own or a fighter in a skirmish
+pub trait Combatant: Entity {
+    fn owner(&self) -> i32;
+    fn alive(&self) -> bool;
+    // docked, docking and undocking ships and ones whose weapon is cooling down can't
+    fn can_fire(&self) -> bool;
+}
+
+impl Combatant for Fighter {
+    fn owner(&self) -> i32 {
+        self.owner_id
+    }
+
+    fn alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    fn can_fire(&self) -> bool {
+        self.alive() && !self.docked && self.cooldown == 0
+    }
+}
+
+impl Combatant for Ship {
+    fn owner(&self) -> i32 {
+        self.owner_id
+    }
+
+    fn alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    fn can_fire(&self) -> bool {
+        self.alive() && self.is_undocked() && self.cooldown == 0
+    }
+}
+
+// The weapon fire at time t the way the engine resolves it: every combatant that can fire splits WEAPON_DAMAGE
+// evenly between all living enemies in range. Returns the indices of those that fired along with the indices of
+// their targets, and the damage each combatant takes. The damage is meant to be dealt all at once, so ones
+// destroyed at this instant still get their shots off
+pub fn resolve_weapons<C: Combatant>(combatants: &[&C], t: f64) -> (Vec<(usize, Vec<usize>)>, Vec<i32>) {
+    let mut shots = vec![];
+    let mut damage = vec![0; combatants.len()];
+    for (i, attacker) in combatants.iter().enumerate() {
+        if !attacker.can_fire() {
+            continue;
+        }
+        let targets: Vec<usize> = combatants
+            .iter()
+            .enumerate()
+            .filter(|&(_, target)| {
+                target.alive() && target.owner() != attacker.owner()
+                    && attacker.dist_to_at(*target, t) <= attack_radius()
+            })
+            .map(|(j, _)| j)
+            .collect();
+        if targets.is_empty() {
+            continue;
+        }
+        for &j in targets.iter() {
+            damage[j] += WEAPON_DAMAGE / targets.len() as i32;
+        }
+        shots.push((i, targets));
+    }
+    (shots, damage)
+}
+
+impl Entity for Fighter {
+    fn get_position(&self) -> Position {
+        self.position
+    }
+
+    fn get_position_at(&self, t: f64) -> Position {
+        Position(self.position.0 + t * self.velocity.0, self.position.1 + t * self.velocity.1)
+    }
+
+    fn get_radius(&self) -> f64 {
+        SHIP_RADIUS
+    }
+}
+
+// A fight between a handful of ships, resolved turn by turn the way the engine resolves weapon fire: at the start
+// of the turn and whenever an enemy comes into range during movement, every ship with its weapon ready splits
+// WEAPON_DAMAGE evenly between all enemies in range, all at once. Every ship keeps the velocity it was given,
+// turn after turn. Collisions, planets and the edges of the map are left out
+pub struct Skirmish {
+    fighters: Vec<Fighter>,
+}
+
+impl Skirmish {
+    pub fn new(ships: &[&Ship]) -> Skirmish {
+        Skirmish {
+            fighters: ships.iter().map(|s| Fighter::new(s)).collect(),
+        }
+    }
+
+    // every ship that could get into a fight with one at position within the given number of turns
+    pub fn around(game_map: &GameMap, position: &Position, turns: usize) -> Skirmish {
+        // either side can close in at full speed
+        let radius = attack_radius() + 2.0 * MAX_SPEED as f64 * turns as f64;
+        Skirmish::new(&game_map.ships_within(position, radius))
+    }
+
+    // a fight between two groups only, whoever owns the ships in them
+    fn between(ours: &[&Ship], theirs: &[&Ship]) -> Skirmish {
+        let side = |ships: &[&Ship], owner_id| {
+            ships
+                .iter()
+                .map(|s| Fighter {
+                    owner_id,
+                    ..Fighter::new(s)
+                })
+                .collect::<Vec<Fighter>>()
+        };
+        let mut fighters = side(ours, 0);
+        fighters.extend(side(theirs, 1));
+        Skirmish { fighters }
+    }
+
+    // overrides a ship's velocity, e.g. with the thrust it's about to be given
+    pub fn set_velocity(&mut self, id: i32, velocity: (f64, f64)) {
+        if let Some(fighter) = self.fighters.iter_mut().find(|f| f.id == id) {
+            fighter.velocity = velocity;
+        }
+    }
+
+    // every ship that can fly heads straight for the nearest enemy still standing, stopping half a ship inside
+    // weapon range of where that enemy is now
+    pub fn close_in(&mut self) {
+        let velocities: Vec<(f64, f64)> = self.fighters
+            .iter()
+            .map(|fighter| {
+                if !fighter.alive() || fighter.docked {
+                    return (0.0, 0.0);
+                }
+                let target = self.fighters
+                    .iter()
+                    .filter(|f| f.alive() && f.owner_id != fighter.owner_id)
+                    .min_by(|f1, f2| {
+                        fighter
+                            .distance_to(*f1)
+                            .partial_cmp(&fighter.distance_to(*f2))
+                            .unwrap()
+                    });
+                match target {
+                    Some(target) => {
+                        let distance = fighter.distance_to(target);
+                        let speed = (distance - attack_radius() + SHIP_RADIUS).max(0.0).min(MAX_SPEED as f64);
+                        (
+                            speed * (target.position.0 - fighter.position.0) / distance,
+                            speed * (target.position.1 - fighter.position.1) / distance,
+                        )
+                    }
+                    None => (0.0, 0.0),
+                }
+            })
+            .collect();
+        for (fighter, velocity) in self.fighters.iter_mut().zip(velocities) {
+            fighter.velocity = velocity;
+        }
+    }
+
+    pub fn step(&mut self) {
+        // ships already in range of each other fight at the very start of the turn
+        let mut times = vec![0.0];
+        for (i, fighter) in self.fighters.iter().enumerate() {
+            for other in self.fighters.iter().skip(i + 1) {
+                if fighter.owner_id != other.owner_id && (fighter.can_fire() || other.can_fire()) {
+                    times.extend(contact_time(fighter, other, attack_radius()));
+                }
+            }
+        }
+        times.sort_by(|t1, t2| t1.partial_cmp(t2).unwrap());
+        times.dedup();
+        for t in times {
+            self.fire(t);
+        }
+        for fighter in self.fighters.iter_mut() {
+            fighter.position = fighter.get_position_at(1.0);
+            fighter.cooldown = max(0, fighter.cooldown - 1);
+        }
+    }
+
+    fn fire(&mut self, t: f64) {
+        let (shots, damage) = resolve_weapons(&self.fighters.iter().collect::<Vec<_>>(), t);
+        for (i, _) in shots {
+            self.fighters[i].cooldown = WEAPON_COOLDOWN;
+        }
+        for (fighter, d) in self.fighters.iter_mut().zip(damage) {
+            fighter.hp -= d;
+        }
+    }
+
+    pub fn run(&mut self, turns: usize) {
+        for _ in 0..turns {
+            self.step();
+        }
+    }
+
+    // the ship's hp so far, 0 once destroyed and None for a ship not in the fight
+    pub fn hp(&self, id: i32) -> Option<i32> {
+        self.fighters.iter().find(|f| f.id == id).map(|f| max(0, f.hp))
+    }
+
+    pub fn fighters(&self) -> &[Fighter] {
+        &self.fighters
+    }
+
+    fn standing(&self, owner_id: i32) -> Vec<&Fighter> {
+        self.fighters
+            .iter()
+            .filter(|f| f.alive() && f.owner_id == owner_id)
+            .collect()
+    }
+}
+
+// turns an engagement is followed for before calling it undecided
+pub const ENGAGEMENT_TURNS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+// how a fight between two groups of ships plays out, everyone closing in on the nearest enemy every turn
+#[derive(Debug, Clone)]
+pub struct Engagement {
+    // None when both groups go down on the same turn, or when the fight isn't decided in time
+    pub winner: Option<Side>,
+    pub our_ships: usize,
+    pub their_ships: usize,
+    pub our_survivors: usize,
+    pub their_survivors: usize,
+    pub our_hp: i32,
+    pub their_hp: i32,
+    // turns until one group has been wiped out, None if neither was within the turns followed
+    pub turns: Option<usize>,
+}
+
+impl Engagement {
+    pub fn won(&self) -> bool {
+        self.winner == Some(Side::Ours)
+    }
+
+    pub fn our_losses(&self) -> usize {
+        self.our_ships - self.our_survivors
+    }
+
+    pub fn their_losses(&self) -> usize {
+        self.their_ships - self.their_survivors
+    }
+}
+
+pub fn engage(ours: &[&Ship], theirs: &[&Ship], max_turns: usize) -> Engagement {
+    let mut skirmish = Skirmish::between(ours, theirs);
+    let mut turns = None;
+    for turn in 0..max_turns + 1 {
+        if skirmish.standing(0).is_empty() || skirmish.standing(1).is_empty() {
+            turns = Some(turn);
+            break;
+        }
+        if turn < max_turns {
+            skirmish.close_in();
+            skirmish.step();
+        }
+    }
+
+    let (ours_standing, theirs_standing) = (skirmish.standing(0), skirmish.standing(1));
+    let winner = match (turns, ours_standing.is_empty(), theirs_standing.is_empty()) {
+        (Some(_), false, true) => Some(Side::Ours),
+        (Some(_), true, false) => Some(Side::Theirs),
+        _ => None,
+    };
+    Engagement {
+        winner,
+        our_ships: ours.len(),
+        their_ships: theirs.len(),
+        our_survivors: ours_standing.len(),
+        their_survivors: theirs_standing.len(),
+        our_hp: ours_standing.iter().map(|f| f.hp).sum(),
+        their_hp: theirs_standing.iter().map(|f| f.hp).sum(),
+        turns,
+    }
+}
+
+// the fight the ship is in: every ship of its owner around it against every other ship around it
+pub fn local_engagement(ship: &Ship, game_map: &GameMap) -> Engagement {
+    let (ours, theirs): (Vec<&Ship>, Vec<&Ship>) = game_map
+        .ships_within(&ship.get_position(), attack_radius() + 2.0 * 2.0 * MAX_SPEED as f64)
+        .into_iter()
+        .partition(|s| s.owner_id == ship.owner_id);
+    engage(&ours, &theirs, ENGAGEMENT_TURNS)
+}
+
+// the ship's hp after the given number of turns of fighting everything around it, at the velocities they have now
+pub fn hp_after(ship: &Ship, game_map: &GameMap, turns: usize) -> i32 {
+    let mut skirmish = Skirmish::around(game_map, &ship.get_position(), turns);
+    skirmish.run(turns);
+    skirmish.hp(ship.id).unwrap_or(ship.hp)
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::combat::{engage, Side, Skirmish, ENGAGEMENT_TURNS};
+    use hlt::constants::{BASE_SHIP_HEALTH, WEAPON_DAMAGE};
+    use hlt::entity::{DockingStatus, Position, Ship};
+
+    fn ship(id: i32, owner_id: i32, x: f64, y: f64) -> Ship {
+        Ship::new(id, owner_id, Position(x, y))
+    }
+
+    #[test]
+    fn test_damage_is_split() {
+        // ship 0 has both enemies in range, each of them only ship 0
+        let ships = [ship(0, 0, 50.0, 50.0), ship(1, 1, 54.0, 50.0), ship(2, 1, 46.0, 50.0)];
+        let mut skirmish = Skirmish::new(&ships.iter().collect::<Vec<&Ship>>());
+        skirmish.step();
+        assert_eq!(Some(BASE_SHIP_HEALTH - 2 * WEAPON_DAMAGE), skirmish.hp(0));
+        assert_eq!(Some(BASE_SHIP_HEALTH - WEAPON_DAMAGE / 2), skirmish.hp(1));
+        assert_eq!(Some(BASE_SHIP_HEALTH - WEAPON_DAMAGE / 2), skirmish.hp(2));
+        assert_eq!(None, skirmish.hp(3));
+        // ship 0 goes down on the second turn, having split its last shot again
+        skirmish.step();
+        assert_eq!(Some(0), skirmish.hp(0));
+        assert_eq!(Some(BASE_SHIP_HEALTH - WEAPON_DAMAGE), skirmish.hp(1));
+    }
+
+    #[test]
+    fn test_docked_ships_dont_fire() {
+        let mut docked = ship(1, 1, 54.0, 50.0);
+        docked.docking_status = DockingStatus::DOCKED;
+        let ships = [ship(0, 0, 50.0, 50.0), docked];
+        let mut skirmish = Skirmish::new(&ships.iter().collect::<Vec<&Ship>>());
+        skirmish.run(4);
+        assert_eq!(Some(BASE_SHIP_HEALTH), skirmish.hp(0));
+        assert_eq!(Some(0), skirmish.hp(1));
+    }
+
+    #[test]
+    fn test_closing_in_and_cooldown() {
+        // out of range at first, in range part way through the turn
+        let ships = [ship(0, 0, 50.0, 50.0), ship(1, 1, 59.0, 50.0)];
+        let mut skirmish = Skirmish::new(&ships.iter().collect::<Vec<&Ship>>());
+        skirmish.set_velocity(1, (-6.0, 0.0));
+        skirmish.step();
+        assert_eq!(Some(BASE_SHIP_HEALTH - WEAPON_DAMAGE), skirmish.hp(0));
+        assert_eq!(Some(BASE_SHIP_HEALTH - WEAPON_DAMAGE), skirmish.hp(1));
+
+        // a ship whose weapon is cooling down only fires once it's ready
+        let mut cooling = ship(1, 1, 54.0, 50.0);
+        cooling.cooldown = 2;
+        let ships = [ship(0, 0, 50.0, 50.0), cooling];
+        let mut skirmish = Skirmish::new(&ships.iter().collect::<Vec<&Ship>>());
+        skirmish.run(3);
+        assert_eq!(Some(BASE_SHIP_HEALTH - WEAPON_DAMAGE), skirmish.hp(0));
+        assert_eq!(Some(BASE_SHIP_HEALTH - 3 * WEAPON_DAMAGE), skirmish.hp(1));
+    }
+
+    #[test]
+    fn test_engagement_outnumbered() {
+        // two against one, starting well out of range of each other
+        let ships = [ship(0, 0, 50.0, 50.0), ship(1, 0, 50.0, 53.0), ship(2, 1, 80.0, 50.0)];
+        let fight = engage(&[&ships[0], &ships[1]], &[&ships[2]], ENGAGEMENT_TURNS);
+        assert_eq!(Some(Side::Ours), fight.winner);
+        assert!(fight.won());
+        assert_eq!((2, 0), (fight.our_survivors, fight.their_survivors));
+        assert_eq!(0, fight.their_hp);
+        assert!(fight.our_hp < 2 * BASE_SHIP_HEALTH);
+        // a few turns to close the distance, then two volleys of two
+        assert!(fight.turns.unwrap() > 2 && fight.turns.unwrap() < ENGAGEMENT_TURNS);
+
+        let reversed = engage(&[&ships[2]], &[&ships[0], &ships[1]], ENGAGEMENT_TURNS);
+        assert_eq!(Some(Side::Theirs), reversed.winner);
+        assert_eq!(1, reversed.our_losses());
+        assert_eq!(fight.turns, reversed.turns);
+    }
+
+    #[test]
+    fn test_engagement_even_and_undecided() {
+        // an even fight takes both ships down together
+        let ships = [ship(0, 0, 50.0, 50.0), ship(1, 1, 60.0, 50.0)];
+        let fight = engage(&[&ships[0]], &[&ships[1]], ENGAGEMENT_TURNS);
+        assert_eq!(None, fight.winner);
+        assert_eq!((1, 1), (fight.our_losses(), fight.their_losses()));
+        assert!(fight.turns.is_some());
+
+        // docked ships never close in nor fire, so nothing happens
+        let mut docked = [ship(0, 0, 50.0, 50.0), ship(1, 1, 60.0, 50.0)];
+        for s in docked.iter_mut() {
+            s.docking_status = DockingStatus::DOCKED;
+        }
+        let fight = engage(&[&docked[0]], &[&docked[1]], ENGAGEMENT_TURNS);
+        assert_eq!(None, fight.winner);
+        assert_eq!(None, fight.turns);
+        assert_eq!((BASE_SHIP_HEALTH, BASE_SHIP_HEALTH), (fight.our_hp, fight.their_hp));
+
+        // but a docked ship is easy prey, and nobody to fight is an immediate win
+        let fight = engage(&[&ships[0]], &[&docked[1]], ENGAGEMENT_TURNS);
+        assert!(fight.won());
+        assert_eq!(BASE_SHIP_HEALTH, fight.our_hp);
+        assert_eq!(Some(0), engage(&[&ships[0]], &[], ENGAGEMENT_TURNS).turns);
+    }
+}
@@ -1,3 +1,10 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use hlt::constants::MAX_SPEED;
+use hlt::entity::Ship;
+use hlt::game_map::GameMap;
+
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Command {
@@ -9,18 +16,123 @@ pub enum Command {
 
 impl Command {
     pub fn encode(&self) -> String {
-        return match self {
-            &Command::Dock(ship, planet) => format!("d {} {}", ship, planet),
-            &Command::Undock(ship) => format!("u {}", ship),
-            &Command::Thrust(ship, magnitude, angle) => format!("t {} {} {}", ship, magnitude, angle),
-            &Command::Stay() => format!(""),
+        match *self {
+            Command::Dock(ship, planet) => format!("d {} {}", ship, planet),
+            Command::Undock(ship) => format!("u {}", ship),
+            Command::Thrust(ship, magnitude, angle) => format!("t {} {} {}", ship, magnitude, angle),
+            Command::Stay() => String::new(),
+        }
+    }
+
+    pub fn ship_id(&self) -> Option<i32> {
+        match *self {
+            Command::Dock(ship, _) | Command::Undock(ship) | Command::Thrust(ship, _, _) => Some(ship),
+            Command::Stay() => None,
+        }
+    }
+}
+
+// why a command was left out of a turn
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Rejection {
+    NotMyShip,
+    DuplicateShip,
+    SpeedOutOfRange,
+    AngleOutOfRange,
+    ThrustWhileDocked,
+    AlreadyDocked,
+    NoSuchPlanet,
+    OutOfDockRange,
+    NotDocked,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match *self {
+            Rejection::NotMyShip => "not one of my ships",
+            Rejection::DuplicateShip => "ship already has a command this turn",
+            Rejection::SpeedOutOfRange => "thrust magnitude outside 0..=MAX_SPEED",
+            Rejection::AngleOutOfRange => "thrust angle outside 0..360",
+            Rejection::ThrustWhileDocked => "docked ships can not thrust",
+            Rejection::AlreadyDocked => "ship is already docked",
+            Rejection::NoSuchPlanet => "no such planet",
+            Rejection::OutOfDockRange => "planet is out of dock range",
+            Rejection::NotDocked => "ship is not docked",
         };
+        write!(f, "{}", reason)
     }
 }
 
+fn check(command: &Command, ship: &Ship, game_map: &GameMap) -> Result<(), Rejection> {
+    match *command {
+        Command::Thrust(_, magnitude, angle) => {
+            if !(0..=MAX_SPEED).contains(&magnitude) {
+                Err(Rejection::SpeedOutOfRange)
+            } else if !(0..360).contains(&angle) {
+                Err(Rejection::AngleOutOfRange)
+            } else if !ship.is_undocked() {
+                Err(Rejection::ThrustWhileDocked)
+            } else {
+                Ok(())
+            }
+        }
+        Command::Dock(_, planet_id) => match game_map.get_planet(planet_id) {
+            None => Err(Rejection::NoSuchPlanet),
+            Some(_) if !ship.is_undocked() => Err(Rejection::AlreadyDocked),
+            Some(planet) if !ship.in_dock_range(planet) => Err(Rejection::OutOfDockRange),
+            Some(_) => Ok(()),
+        },
+        // the engine ignores undocking ships which are still docking or already undocking
+        Command::Undock(_) if ship.is_docked() => Ok(()),
+        Command::Undock(_) => Err(Rejection::NotDocked),
+        Command::Stay() => Ok(()),
+    }
+}
+
+// Splits a turn's commands into the ones worth sending and the ones the engine would refuse or that contradict an
+// earlier command for the same ship. Stays are no-ops and are dropped without being counted as rejected
+pub fn validate_commands(commands: &[Command], game_map: &GameMap) -> (Vec<Command>, Vec<(Command, Rejection)>) {
+    let mut accepted: Vec<Command> = vec![];
+    let mut rejected = vec![];
+    let mut commanded = HashSet::new();
+    for command in commands.iter() {
+        let ship_id = match command.ship_id() {
+            Some(ship_id) => ship_id,
+            None => continue,
+        };
+        let result = match game_map.find_ship(ship_id).filter(|s| s.owner_id == game_map.get_me().id) {
+            None => Err(Rejection::NotMyShip),
+            Some(_) if commanded.contains(&ship_id) => Err(Rejection::DuplicateShip),
+            Some(ship) => check(command, ship, game_map),
+        };
+        match result {
+            Ok(()) => {
+                commanded.insert(ship_id);
+                accepted.push(*command)
+            }
+            Err(rejection) => rejected.push((*command, rejection)),
+        }
+    }
+    (accepted, rejected)
+}
+
+// the command line for a turn, without the trailing newline
+pub fn encode_commands(commands: &[Command]) -> String {
+    commands
+        .iter()
+        .map(|c| c.encode())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
 #[cfg(test)]
 mod tests {
-    use command::Command;
+    use hlt::command::{encode_commands, validate_commands, Command, Rejection};
+    use hlt::entity::{DockingStatus, GameState, Planet, Position, Ship};
+    use hlt::game::Game;
+    use hlt::game_map::GameMap;
+    use hlt::player::Player;
 
     #[test]
     fn test_thing() {
@@ -28,4 +140,82 @@ mod tests {
         assert_eq!("t 3 9 4", Command::Thrust(3, 9, 4).encode());
         assert_eq!("u 3", Command::Undock(3).encode());
     }
+
+    #[test]
+    fn test_encode_commands() {
+        let commands = vec![Command::Thrust(1, 7, 90), Command::Stay(), Command::Dock(2, 3), Command::Undock(4)];
+        assert_eq!("t 1 7 90 d 2 3 u 4", encode_commands(&commands));
+        assert_eq!("", encode_commands(&[Command::Stay()]));
+    }
+
+    #[test]
+    fn test_validate_commands() {
+        let game = Game::offline(0, 240, 160);
+        let mut docked = Ship::new(2, 0, Position(50.0, 50.0));
+        docked.docking_status = DockingStatus::DOCKED;
+        docked.docked_planet = Some(0);
+        let mut docking = Ship::new(4, 0, Position(50.0, 59.0));
+        docking.docking_status = DockingStatus::DOCKING;
+        docking.docked_planet = Some(0);
+        let state = GameState {
+            players: vec![
+                Player {
+                    id: 0,
+                    ships: vec![
+                        Ship::new(0, 0, Position(47.0, 52.0)),
+                        Ship::new(1, 0, Position(10.0, 10.0)),
+                        docked,
+                        docking,
+                    ],
+                    strength: 0.0,
+                },
+                Player {
+                    id: 1,
+                    ships: vec![Ship::new(3, 1, Position(100.0, 100.0))],
+                    strength: 0.0,
+                },
+            ],
+            planets: vec![Planet::new(0, Position(50.0, 55.0), 1000, 3.0, 3, 1000)],
+        };
+        let game_map = GameMap::new(&game, state);
+
+        let commands = vec![
+            Command::Dock(0, 0),
+            Command::Thrust(0, 7, 90),
+            Command::Stay(),
+            Command::Thrust(1, 8, 90),
+            Command::Thrust(1, 7, 360),
+            Command::Thrust(1, 7, 359),
+            Command::Thrust(2, 7, 0),
+            Command::Undock(2),
+            Command::Thrust(3, 7, 0),
+            Command::Undock(4),
+        ];
+        let (accepted, rejected) = validate_commands(&commands, &game_map);
+        assert_eq!(
+            vec![Command::Dock(0, 0), Command::Thrust(1, 7, 359), Command::Undock(2)],
+            accepted
+        );
+        assert_eq!(
+            vec![
+                (Command::Thrust(0, 7, 90), Rejection::DuplicateShip),
+                (Command::Thrust(1, 8, 90), Rejection::SpeedOutOfRange),
+                (Command::Thrust(1, 7, 360), Rejection::AngleOutOfRange),
+                (Command::Thrust(2, 7, 0), Rejection::ThrustWhileDocked),
+                (Command::Thrust(3, 7, 0), Rejection::NotMyShip),
+                (Command::Undock(4), Rejection::NotDocked),
+            ],
+            rejected
+        );
+
+        let (accepted, rejected) = validate_commands(&[Command::Dock(1, 0), Command::Dock(0, 5)], &game_map);
+        assert!(accepted.is_empty());
+        assert_eq!(
+            vec![
+                (Command::Dock(1, 0), Rejection::OutOfDockRange),
+                (Command::Dock(0, 5), Rejection::NoSuchPlanet),
+            ],
+            rejected
+        );
+    }
 }
@@ -0,0 +1,278 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use serde_json::{self, Map, Value};
+
+use hlt::constants::{DEFEND_PREFERENCE_2P, DEFEND_PREFERENCE_4P, DOCK_PREFERENCE_2P, DOCK_PREFERENCE_4P,
+                     INTERCEPT_PREFERENCE_2P, INTERCEPT_PREFERENCE_4P, RAID_PREFERENCE_2P, RAID_PREFERENCE_4P};
+
+// every tunable, by the name used in config files and key=value arguments
+pub const KEYS: [&str; 20] = [
+    "dock_preference_2p",
+    "raid_preference_2p",
+    "defend_preference_2p",
+    "intercept_preference_2p",
+    "dock_preference_4p",
+    "raid_preference_4p",
+    "defend_preference_4p",
+    "intercept_preference_4p",
+    "time_cutoff_ms",
+    "move_time_cutoff_ms",
+    "flee_threshold",
+    "per_ship_multiplier",
+    "size_factor_2",
+    "size_factor_3",
+    "size_factor_4",
+    "size_factor_5",
+    "size_factor_6",
+    "edge_scale",
+    "neighbour_dock_weight",
+    "engagement_loss_weight",
+];
+
+// how much each kind of move is worth relative to the others, lower being better
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preferences {
+    pub dock: f64,
+    pub raid: f64,
+    pub defend: f64,
+    pub intercept: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Configs {
+    pub preferences_2p: Preferences,
+    pub preferences_4p: Preferences,
+    // no new ship moves are worked out after this long into the turn
+    pub time_cutoff_ms: f64,
+    // no more commands are handed out after this long
+    pub move_time_cutoff_ms: f64,
+    // in games of more than 2, everyone flees once an enemy fleet is this many times the size of ours
+    pub flee_threshold: f64,
+    // how much more a group of ships is worth than its hp alone, per ship in it
+    pub per_ship_multiplier: f64,
+    // dock value factor of planets with 2 to 6 docking spots
+    pub size_factors: [f64; 5],
+    // how much less planets near the middle of the map are worth than ones at the edges
+    pub edge_scale: f64,
+    // weight of the planets around a planet in its dock value
+    pub neighbour_dock_weight: f64,
+    // how much each of our ships lost taking on a target multiplies its cost by, with every ship of ours and theirs
+    // left standing counting instead when the fight can't be won. At 1.0 losing a ship costs as much as flying
+    // the distance to the target all over again
+    pub engagement_loss_weight: f64,
+}
+
+impl Default for Configs {
+    fn default() -> Configs {
+        Configs {
+            preferences_2p: Preferences {
+                dock: DOCK_PREFERENCE_2P,
+                raid: RAID_PREFERENCE_2P,
+                defend: DEFEND_PREFERENCE_2P,
+                intercept: INTERCEPT_PREFERENCE_2P,
+            },
+            preferences_4p: Preferences {
+                dock: DOCK_PREFERENCE_4P,
+                raid: RAID_PREFERENCE_4P,
+                defend: DEFEND_PREFERENCE_4P,
+                intercept: INTERCEPT_PREFERENCE_4P,
+            },
+            time_cutoff_ms: 1900.0,
+            move_time_cutoff_ms: 1925.0,
+            flee_threshold: 2.0,
+            per_ship_multiplier: 0.15,
+            size_factors: [1.30, 1.10, 1.10, 1.05, 1.00],
+            edge_scale: 0.50,
+            neighbour_dock_weight: 0.5,
+            engagement_loss_weight: 1.0,
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Configs {
+    // the preferences for a game against this many opponents worth worrying about
+    pub fn preferences(&self, relevant_opponents: usize) -> &Preferences {
+        if relevant_opponents > 1 {
+            &self.preferences_4p
+        } else {
+            &self.preferences_2p
+        }
+    }
+
+    pub fn size_factor(&self, num_docking_spots: i32) -> f64 {
+        self.size_factors[(num_docking_spots - 2) as usize]
+    }
+
+    fn field(&mut self, key: &str) -> Option<&mut f64> {
+        Some(match key {
+            "dock_preference_2p" => &mut self.preferences_2p.dock,
+            "raid_preference_2p" => &mut self.preferences_2p.raid,
+            "defend_preference_2p" => &mut self.preferences_2p.defend,
+            "intercept_preference_2p" => &mut self.preferences_2p.intercept,
+            "dock_preference_4p" => &mut self.preferences_4p.dock,
+            "raid_preference_4p" => &mut self.preferences_4p.raid,
+            "defend_preference_4p" => &mut self.preferences_4p.defend,
+            "intercept_preference_4p" => &mut self.preferences_4p.intercept,
+            "time_cutoff_ms" => &mut self.time_cutoff_ms,
+            "move_time_cutoff_ms" => &mut self.move_time_cutoff_ms,
+            "flee_threshold" => &mut self.flee_threshold,
+            "per_ship_multiplier" => &mut self.per_ship_multiplier,
+            "size_factor_2" => &mut self.size_factors[0],
+            "size_factor_3" => &mut self.size_factors[1],
+            "size_factor_4" => &mut self.size_factors[2],
+            "size_factor_5" => &mut self.size_factors[3],
+            "size_factor_6" => &mut self.size_factors[4],
+            "edge_scale" => &mut self.edge_scale,
+            "neighbour_dock_weight" => &mut self.neighbour_dock_weight,
+            "engagement_loss_weight" => &mut self.engagement_loss_weight,
+            _ => return None,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<f64> {
+        Some(match key {
+            "dock_preference_2p" => self.preferences_2p.dock,
+            "raid_preference_2p" => self.preferences_2p.raid,
+            "defend_preference_2p" => self.preferences_2p.defend,
+            "intercept_preference_2p" => self.preferences_2p.intercept,
+            "dock_preference_4p" => self.preferences_4p.dock,
+            "raid_preference_4p" => self.preferences_4p.raid,
+            "defend_preference_4p" => self.preferences_4p.defend,
+            "intercept_preference_4p" => self.preferences_4p.intercept,
+            "time_cutoff_ms" => self.time_cutoff_ms,
+            "move_time_cutoff_ms" => self.move_time_cutoff_ms,
+            "flee_threshold" => self.flee_threshold,
+            "per_ship_multiplier" => self.per_ship_multiplier,
+            "size_factor_2" => self.size_factors[0],
+            "size_factor_3" => self.size_factors[1],
+            "size_factor_4" => self.size_factors[2],
+            "size_factor_5" => self.size_factors[3],
+            "size_factor_6" => self.size_factors[4],
+            "edge_scale" => self.edge_scale,
+            "neighbour_dock_weight" => self.neighbour_dock_weight,
+            "engagement_loss_weight" => self.engagement_loss_weight,
+            _ => return None,
+        })
+    }
+
+    // every value is a weight, factor or time, so NaN, infinities and negative numbers are refused
+    pub fn set(&mut self, key: &str, value: f64) -> io::Result<()> {
+        if !value.is_finite() || value < 0.0 {
+            return Err(invalid(format!("config value of {:?} must be finite and at least 0, got {}", key, value)));
+        }
+        match self.field(key) {
+            Some(field) => {
+                *field = value;
+                Ok(())
+            }
+            None => Err(invalid(format!("unknown config key {:?}", key))),
+        }
+    }
+
+    // applies a "key=value" override
+    pub fn apply(&mut self, assignment: &str) -> io::Result<()> {
+        let mut parts = assignment.splitn(2, '=');
+        let key = parts.next().unwrap().trim();
+        match parts.next().map(|v| v.trim().parse()) {
+            Some(Ok(value)) => self.set(key, value),
+            _ => Err(invalid(format!("expected key=number, got {:?}", assignment))),
+        }
+    }
+
+    // the defaults overridden by a JSON object of keys to numbers, which needn't name every key
+    pub fn from_json(json: &Value) -> io::Result<Configs> {
+        let mut configs = Configs::default();
+        let object = json
+            .as_object()
+            .ok_or_else(|| invalid("config is not a JSON object".to_string()))?;
+        for (key, value) in object.iter() {
+            match value.as_f64() {
+                Some(value) => configs.set(key, value)?,
+                None => return Err(invalid(format!("config value of {:?} is not a number", key))),
+            }
+        }
+        Ok(configs)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Configs> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        let json = serde_json::from_str(&text).map_err(|e| invalid(format!("{}: {}", path.display(), e)))?;
+        Configs::from_json(&json)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", serde_json::to_string_pretty(&self.to_json()).unwrap())
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        for key in KEYS.iter() {
+            object.insert(key.to_string(), json!(self.get(key).unwrap()));
+        }
+        Value::Object(object)
+    }
+}
+
+// one key=value per line, which apply reads back
+impl fmt::Display for Configs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for key in KEYS.iter() {
+            writeln!(f, "{}={}", key, self.get(key).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::config::{Configs, KEYS};
+
+    #[test]
+    fn test_configs() {
+        let mut configs = Configs::default();
+        assert_eq!(Some(0.15), configs.get("per_ship_multiplier"));
+        configs.apply("per_ship_multiplier=0.2").unwrap();
+        configs.apply("size_factor_3 = 1.5").unwrap();
+        assert_eq!(0.2, configs.per_ship_multiplier);
+        assert_eq!(1.5, configs.size_factor(3));
+        assert!(configs.apply("per_ship_multiplier").is_err());
+        assert!(configs.apply("no_such_key=1").is_err());
+        assert!(configs.apply("flee_threshold=x").is_err());
+        for bad in ["NaN", "inf", "-inf", "-1"].iter() {
+            assert!(configs.apply(&format!("flee_threshold={}", bad)).is_err());
+        }
+        // gone rather than quietly standing for something else
+        assert!(configs.apply("defender_weight=0.5").is_err());
+
+        // every key reads back what was set, and nothing else changes with it
+        for (i, key) in KEYS.iter().enumerate() {
+            let mut set = Configs::default();
+            set.set(key, 100.0 + i as f64).unwrap();
+            assert_eq!(Some(100.0 + i as f64), set.get(key));
+            assert_eq!(1, KEYS.iter().filter(|k| set.get(k) != Configs::default().get(k)).count());
+        }
+
+        // every key survives both ways out and back
+        assert_eq!(configs, Configs::from_json(&configs.to_json()).unwrap());
+        let mut applied = Configs::default();
+        for line in configs.to_string().lines() {
+            applied.apply(line).unwrap();
+        }
+        assert_eq!(configs, applied);
+        assert_eq!(KEYS.len(), configs.to_string().lines().count());
+
+        let partial = Configs::from_json(&json!({"raid_preference_4p": 1.0})).unwrap();
+        assert_eq!(1.0, partial.preferences(2).raid);
+        assert_eq!(Configs::default().preferences_2p, partial.preferences_2p);
+        assert!(Configs::from_json(&json!({"raid_preference_4p": "1"})).is_err());
+    }
+}
@@ -2,13 +2,16 @@ use std::cell::Cell;
 use std::cmp::min;
 use std::fmt;
 
+use hlt::angle::Angle;
 use hlt::pathfind::short_angle_around;
-use hlt::parse::Decodable;
-use hlt::logging::Logger;
+use hlt::parse::{Decodable, Encodable, ParseError, Tokens};
+use hlt::combat::{engage, hp_after, ENGAGEMENT_TURNS};
 use hlt::command::Command;
-use hlt::constants::{DOCK_RADIUS, DOCK_TURNS, FUDGE, MAX_EXPLOSION_DAMAGE, MAX_SHIP_HEALTH, MAX_SPEED,
+use hlt::config::Configs;
+use hlt::constants::{BASE_SHIP_HEALTH, DOCK_RADIUS, DOCK_TURNS, FUDGE, MAX_EXPLOSION_DAMAGE, MAX_SHIP_HEALTH, MAX_SPEED,
                      MIN_EXPLOSION_DAMAGE, SHIP_COST, SHIP_RADIUS, WEAPON_RADIUS};
 use hlt::player::Player;
+use hlt::vector::Vec2;
 use std::collections::HashMap;
 use hlt::game_map::GameMap;
 
@@ -23,13 +26,17 @@ impl fmt::Display for Position {
 }
 
 impl Decodable for Position {
-    fn parse<'a, I>(tokens: &mut I) -> Position
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let x = f64::parse(tokens);
-        let y = f64::parse(tokens);
-        return Position(x, y);
+    fn parse(tokens: &mut Tokens) -> Result<Position, ParseError> {
+        let x = f64::parse(tokens)?;
+        let y = f64::parse(tokens)?;
+        Ok(Position(x, y))
+    }
+}
+
+impl Encodable for Position {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.0.encode(tokens);
+        self.1.encode(tokens);
     }
 }
 
@@ -41,7 +48,7 @@ pub struct Obstacle {
     pub velocity_y: f64,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum DockingStatus {
     UNDOCKED = 0,
     DOCKING = 1,
@@ -62,22 +69,29 @@ impl fmt::Display for DockingStatus {
 }
 
 impl Decodable for DockingStatus {
-    fn parse<'a, I>(tokens: &mut I) -> DockingStatus
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let i = i32::parse(tokens);
-        return match i {
-            0 => DockingStatus::UNDOCKED,
-            1 => DockingStatus::DOCKING,
-            2 => DockingStatus::DOCKED,
-            3 => DockingStatus::UNDOCKING,
-            _ => panic!(format!("Not a valid docking status: {:?}", i)),
-        };
+    fn parse(tokens: &mut Tokens) -> Result<DockingStatus, ParseError> {
+        let (index, token) = tokens.next("DockingStatus")?;
+        match token {
+            "0" => Ok(DockingStatus::UNDOCKED),
+            "1" => Ok(DockingStatus::DOCKING),
+            "2" => Ok(DockingStatus::DOCKED),
+            "3" => Ok(DockingStatus::UNDOCKING),
+            _ => Err(ParseError {
+                index,
+                expected: "DockingStatus",
+                found: Some(token.to_string()),
+            }),
+        }
     }
 }
 
-#[derive(Debug)]
+impl Encodable for DockingStatus {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        (*self as i32).encode(tokens);
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Ship {
     pub id: i32,
     pub owner_id: i32,
@@ -93,6 +107,23 @@ pub struct Ship {
 }
 
 impl Ship {
+    // a freshly spawned, undocked ship at full health
+    pub fn new(id: i32, owner_id: i32, position: Position) -> Ship {
+        Ship {
+            id,
+            owner_id,
+            positions: vec![position],
+            hp: BASE_SHIP_HEALTH,
+            velocity_x: Cell::new(0.0),
+            velocity_y: Cell::new(0.0),
+            docking_status: DockingStatus::UNDOCKED,
+            docked_planet: None,
+            progress: 0,
+            cooldown: 0,
+            command: Cell::new(None),
+        }
+    }
+
     pub fn thrust(&self, magnitude: i32, angle: i32) -> Command {
         Command::Thrust(self.id, magnitude, angle)
     }
@@ -124,7 +155,7 @@ impl Ship {
     }
 
     pub fn in_dock_range(&self, planet: &Planet) -> bool {
-        self.distance_to_less_than(planet, (DOCK_RADIUS + planet.get_radius()))
+        self.distance_to_less_than(planet, DOCK_RADIUS + planet.get_radius())
     }
 
     pub fn in_attack_range_at(&self, ship: &Ship, t: f64) -> bool {
@@ -134,7 +165,7 @@ impl Ship {
 
     pub fn enemies_in_attack_range_at(&self, game_map: &GameMap, t: f64) -> usize {
         game_map
-            .all_ships()
+            .ships_within(&self.get_position(), WEAPON_RADIUS + reach(t))
             .iter()
             .filter(|s| {
                 s.owner_id != self.owner_id && s.in_attack_range_at(self, t)
@@ -154,57 +185,63 @@ impl Ship {
         self.hp as f64 / MAX_SHIP_HEALTH as f64
     }
 
-    fn base_dock_value(&self, planet: &Planet, game_map: &GameMap) -> f64 {
-        dock_value_helper(self, planet, game_map)
+    fn base_dock_value(&self, planet: &Planet, game_map: &GameMap, configs: &Configs) -> f64 {
+        dock_value_helper(self, planet, game_map, configs)
     }
 
     // TODO play with dock, raid, intercept, defense heuristics
-    pub fn dock_value(&self, planet: &Planet, game_map: &GameMap) -> f64 {
-        self.base_dock_value(planet, game_map)
-            + (0.5
+    pub fn dock_value(&self, planet: &Planet, game_map: &GameMap, configs: &Configs) -> f64 {
+        self.base_dock_value(planet, game_map, configs)
+            + (configs.neighbour_dock_weight
                 * game_map
                     .all_planets()
                     .iter()
                     .filter(|p| p.id != planet.id)
-                    .map(|p| planet.base_dock_value(p, game_map))
+                    .map(|p| planet.base_dock_value(p, game_map, configs))
                     .filter(|v| v < &1000.0)
                     .fold(0.0, |acc, s| acc + s) / game_map.all_planets().len() as f64)
     }
 
-    pub fn raid_value(&self, enemy_ship: &Ship, game_map: &GameMap, commitment_map: &HashMap<i32, Vec<i32>>) -> f64 {
-        let defense_factor = 1.00 + (0.50 * total_ship_strength(enemy_ship.defenders(game_map).as_slice()));
-        (1.0 * commitment(enemy_ship, commitment_map) + 1.0) * self.distance_to_surface(enemy_ship)
+    pub fn raid_value(
+        &self,
+        enemy_ship: &Ship,
+        game_map: &GameMap,
+        commitment_map: &HashMap<i32, Vec<i32>>,
+        configs: &Configs,
+    ) -> f64 {
+        let mut targets = enemy_ship.defenders(game_map);
+        if !enemy_ship.is_undocked() {
+            targets.push(enemy_ship);
+        }
+        let defense_factor = self.engagement_factor(&targets, game_map, configs);
+        (1.0 * commitment(enemy_ship, commitment_map, configs) + 1.0) * self.distance_to_surface(enemy_ship)
             * (0.5 + (enemy_ship.hp_percent() / 2.0)) * defense_factor
     }
 
-    pub fn intercept_value(&self, enemy_ship: &Ship, commitment_map: &HashMap<i32, Vec<i32>>) -> f64 {
-        let c = commitment(enemy_ship, commitment_map);
-        let c = if c > enemy_ship.hp_percent() {
-            99999.0
-        } else {
-            1.0 + c
-        };
-        (1.0 * commitment(enemy_ship, commitment_map) + 3.0) * self.distance_to_surface(enemy_ship)
+    pub fn intercept_value(&self, enemy_ship: &Ship, commitment_map: &HashMap<i32, Vec<i32>>, configs: &Configs) -> f64 {
+        (1.0 * commitment(enemy_ship, commitment_map, configs) + 3.0) * self.distance_to_surface(enemy_ship)
             * scaled_to(0.75, enemy_ship.hp_percent())
     }
 
-    pub fn defense_value(&self, enemy_ship: &Ship, game_map: &GameMap, commitment_map: &HashMap<i32, Vec<i32>>) -> f64 {
-        let my_docked_ships: Vec<&Ship> = game_map
-            .my_ships()
-            .into_iter()
-            .filter(|s| !s.is_undocked())
-            .collect();
+    pub fn defense_value(
+        &self,
+        enemy_ship: &Ship,
+        game_map: &GameMap,
+        commitment_map: &HashMap<i32, Vec<i32>>,
+        configs: &Configs,
+    ) -> f64 {
+        let my_docked_ships = game_map.my_docked_ships();
 
-        if my_docked_ships.len() > 0 {
+        if !my_docked_ships.is_empty() {
             // TODO: avg distance to all docked friendly ships?
             let nearest_docked_ship = enemy_ship.nearest_entity(my_docked_ships.as_slice());
             let threat = enemy_ship.distance_to_surface(nearest_docked_ship)
                 * scaled_to(0.66667, nearest_docked_ship.hp_percent());
             let distance_to_victim = self.distance_to_surface(nearest_docked_ship);
-            let distance_to_aggressor = self.distance_to_surface(enemy_ship);
-            let c = commitment(enemy_ship, commitment_map);
+            let c = commitment(enemy_ship, commitment_map, configs);
             let c = if c > 1.0 { 9999.0 } else { 1.0 + c };
             c * ((distance_to_victim * 0.5) + (threat * 1.5))
+                * self.engagement_factor(&enemy_ship.defenders(game_map), game_map, configs)
         } else {
             // if I have no docked ships, there's nothing to defend, unless I can attempt
             // to preemptively defend ships which are going to dock
@@ -212,6 +249,18 @@ impl Ship {
         }
     }
 
+    // what taking on the targets together with our ships nearby costs: 1.0 for a won fight without losses, more for
+    // every ship lost winning it, and for every ship of ours and theirs left standing if it can't be won
+    fn engagement_factor(&self, targets: &[&Ship], game_map: &GameMap, configs: &Configs) -> f64 {
+        let fight = engage(&self.defenders(game_map), targets, ENGAGEMENT_TURNS);
+        let losses = if fight.won() {
+            fight.our_losses()
+        } else {
+            fight.our_ships + fight.their_survivors
+        };
+        1.00 + configs.engagement_loss_weight * losses as f64
+    }
+
     pub fn commanded(&self) -> bool {
         self.command.get().is_some()
     }
@@ -234,9 +283,42 @@ impl Ship {
         self.velocity_y.set(0f64);
     }
 
+    // how far the ship moved last turn, from its position history. Velocity doesn't carry over between turns, so
+    // this is the thrust it was given. None for a ship seen only once
+    pub fn estimated_velocity(&self) -> Option<(f64, f64)> {
+        self.velocity_before(0)
+    }
+
+    // how much the ship's velocity changed last turn, None until it has been seen three times
+    pub fn estimated_acceleration(&self) -> Option<(f64, f64)> {
+        let (v_x, v_y) = self.velocity_before(0)?;
+        let (previous_x, previous_y) = self.velocity_before(1)?;
+        Some((v_x - previous_x, v_y - previous_y))
+    }
+
+    // direction the ship moved in last turn, None if it didn't move
+    pub fn estimated_heading(&self) -> Option<Angle> {
+        let (v_x, v_y) = self.estimated_velocity()?;
+        if v_x.hypot(v_y) < 1e-9 {
+            None
+        } else {
+            Some(Position(v_x, v_y).angle())
+        }
+    }
+
+    // the velocity the given number of turns before the last one
+    fn velocity_before(&self, turns: usize) -> Option<(f64, f64)> {
+        let n = self.positions.len();
+        if n < turns + 2 {
+            return None;
+        }
+        let velocity = self.positions[n - turns - 1] - self.positions[n - turns - 2];
+        Some((velocity.0, velocity.1))
+    }
+
     pub fn defenders<'a>(&self, game_map: &'a GameMap) -> Vec<&'a Ship> {
         game_map
-            .all_ships()
+            .ships_within(&self.get_position(), MAX_SPEED as f64)
             .into_iter()
             .filter(|s| {
                 s.owner_id == self.owner_id && s.is_undocked() && s.distance_to_less_than(self, MAX_SPEED as f64)
@@ -244,52 +326,13 @@ impl Ship {
             .collect()
     }
 
+    // hp lost over the next turn, fighting everything around at the velocities they have now
     pub fn projected_damage_taken(&self, game_map: &GameMap) -> i32 {
-        game_map
-            .all_ships()
-            .into_iter()
-            .filter(|s| s.is_undocked() && s.owner_id != self.owner_id)
-            .map(|enemy| {
-                if enemy.in_attack_range_at(self, 0.0) {
-                    64 / enemy.enemies_in_attack_range_at(game_map, 0.0)
-                } else if enemy.will_enter_attack_range(self) {
-                    64
-                } else {
-                    0
-                }
-            })
-            .fold(0, |acc, s| acc + s as i32)
+        self.hp - hp_after(self, game_map, 1)
     }
 
     pub fn projected_damage_taken_two_turns(&self, game_map: &GameMap) -> i32 {
-        game_map
-            .all_ships()
-            .into_iter()
-            .filter(|s| s.is_undocked() && s.owner_id != self.owner_id)
-            //TODO: check for spawning ships?
-            //TODO: account for destroyed friendly ships on turn two
-            //TODO: check for whether enemy ship will use its attack on anoterh ship before getting
-            // into range
-            .map(|enemy| {
-                if enemy.in_attack_range_at(self, 0.0) {
-                    2 * 64 / enemy.enemies_in_attack_range_at(game_map, 0.0)
-                } else if enemy.will_enter_attack_range(self) {
-                    let turn_2_dmg =
-                        if enemy.in_attack_range_at(self, 1.0) {
-                            64 / enemy.enemies_in_attack_range_at(game_map, 1.0)
-                        } else { 0 };
-                    64 + turn_2_dmg
-                } else if match collision_times(&self.get_obstacle(), &enemy.get_danger_obstacle()) {
-                        Some((t1, _t2)) => t1 > 1.0 && t1 < 2.0,
-                        None => false,
-                    }
-                {
-                    64
-                } else {
-                    0
-                }
-            })
-            .fold(0, |acc, s| acc + s as i32)
+        self.hp - hp_after(self, game_map, 2)
     }
 
     pub fn route_to<T: Entity>(&self, target: &T, game_map: &GameMap) -> (i32, i32) {
@@ -298,13 +341,16 @@ impl Ship {
         let distance = self.distance_to(target);
         let closest_stationary_obstacle: Option<Obstacle> =
             game_map.closest_stationary_obstacle(&self.get_position(), &target.get_position(), FUDGE);
-        let desired_trajectory = match closest_stationary_obstacle {
-            Some(obstacle) => {
+        let waypoint = game_map.waypoint(&self.get_position(), &target.get_position());
+        let desired_trajectory = match (waypoint, closest_stationary_obstacle) {
+            (Some(waypoint), _) => Angle::between(&self.get_position(), &waypoint),
+            // no way around everything, so just steer around what's nearest
+            (None, Some(obstacle)) => {
                 // the ship is already inside the obstacle. Should only happen when the
                 // obstacle is
                 // a planet which will explode. In which case, fly directly away
                 if self.distance_to(&obstacle.position) < obstacle.radius {
-                    obstacle.position.calculate_angle_between(self)
+                    Angle::between(&obstacle.position, &self.get_position())
                 } else {
                     short_angle_around(
                         self.get_position(),
@@ -314,13 +360,10 @@ impl Ship {
                     )
                 }
             }
-            None => self.calculate_angle_between(target),
+            (None, None) => Angle::between(&self.get_position(), &target.get_position()),
         };
         let thrust_speed = min(speed, distance.round() as i32);
-        (
-            thrust_speed,
-            (desired_trajectory.round() as i32 + 360) % 360,
-        )
+        (thrust_speed, desired_trajectory.to_engine())
     }
 
     pub fn will_enter_attack_range(&self, other_ship: &Ship) -> bool {
@@ -341,40 +384,33 @@ impl Ship {
         obstacles: Vec<Obstacle>,
         allow_noops: bool,
     ) -> Option<(i32, i32)> {
-        // first adjust destination to route around planets
+        // first adjust destination to route around planets and docked ships
         let closest_stationary_obstacle: Option<Obstacle> =
             game_map.closest_stationary_obstacle(&self.get_position(), destination, FUDGE);
-        let desired_trajectory = match closest_stationary_obstacle {
-            Some(obstacle) => {
-                // the ship is already inside the obstacle. Should only happen when the
-                // obstacle is
-                // a planet which will explode. In which case, fly directly away
+        let waypoint = game_map.waypoint(&self.get_position(), destination);
+        let desired_trajectory = match (waypoint, closest_stationary_obstacle) {
+            (Some(waypoint), _) => Angle::between(&self.get_position(), &waypoint),
+            // no way around everything, so just steer around what's nearest
+            (None, Some(obstacle)) => {
+                // the ship is already inside the obstacle, there's no steering out of that so stay put
                 if self.distance_to(&obstacle.position) < obstacle.radius {
-                    panic!("inside something");
-                    obstacle.position.calculate_angle_between(self)
-                } else {
-                    short_angle_around(
-                        self.get_position(),
-                        *destination,
-                        obstacle.position,
-                        SHIP_RADIUS + FUDGE + obstacle.radius,
-                    )
+                    return None;
                 }
+                short_angle_around(
+                    self.get_position(),
+                    *destination,
+                    obstacle.position,
+                    SHIP_RADIUS + FUDGE + obstacle.radius,
+                )
             }
-            None => self.calculate_angle_between(destination),
+            (None, None) => Angle::between(&self.get_position(), destination),
         };
         let thrust_speed = min(MAX_SPEED, self.distance_to(destination).round() as i32);
-        let desired_trajectory = (desired_trajectory.round() as i32 + 360) % 360;
+        // what the engine will actually fly at
+        let desired_trajectory = Angle::degrees(f64::from(desired_trajectory.to_engine()));
         let nav_radius = SHIP_RADIUS + FUDGE;
-        let velocity_x = thrust_speed as f64 * (desired_trajectory as f64).to_radians().cos();
-        let velocity_y = thrust_speed as f64 * (desired_trajectory as f64).to_radians().sin();
-        let destination = {
-            let pos = self.get_position();
-            Position {
-                0: pos.0 + velocity_x,
-                1: pos.1 + velocity_y,
-            }
-        };
+        let velocity = Position::from_polar(thrust_speed as f64, desired_trajectory);
+        let destination = self.get_position() + velocity;
 
         let will_collide = |v_x, v_y| -> bool {
             self.set_velocity(v_x, v_y);
@@ -403,8 +439,8 @@ impl Ship {
             false
         };
 
-        if !will_collide(velocity_x, velocity_y) {
-            return Some((thrust_speed, (desired_trajectory as i32 + 360) % 360));
+        if !will_collide(velocity.0, velocity.1) {
+            return Some((thrust_speed, desired_trajectory.to_engine()));
         }
 
         // sort these by how close they'd leave the ship to the target
@@ -413,45 +449,43 @@ impl Ship {
         if allow_noops {
             possible_thrusts.push((0, 0, self.get_position()));
         }
-        for angle in 0..359 {
+        for angle in 0..360 {
             for speed in 1..(MAX_SPEED + 1) {
-                let v_x = speed as f64 * (angle as f64).to_radians().cos();
-                let v_y = speed as f64 * (angle as f64).to_radians().sin();
-                let thrust_end = Position(self.get_position().0 + v_x, self.get_position().1 + v_y);
+                let thrust_end = self.get_position() + thrust_velocity(speed, angle);
                 possible_thrusts.push((speed, angle, thrust_end));
             }
         }
         possible_thrusts.sort_by(|&(_speed1, _angle1, pos1), &(_speed2, _angle2, pos2)| {
-            ((destination.0 - pos1.0).powi(2) + (destination.1 - pos1.1).powi(2))
-                .partial_cmp(&((destination.0 - pos2.0).powi(2) + (destination.1 - pos2.1).powi(2)))
+            (destination - pos1)
+                .length_squared()
+                .partial_cmp(&(destination - pos2).length_squared())
                 .unwrap()
         });
 
         for (speed, angle, _end_position) in possible_thrusts {
-            let velocity_x = speed as f64 * (angle as f64).to_radians().cos();
-            let velocity_y = speed as f64 * (angle as f64).to_radians().sin();
-            if !will_collide(velocity_x, velocity_y) {
-                return Some((speed, (angle as i32 + 360) % 360));
+            let velocity = thrust_velocity(speed, angle);
+            if !will_collide(velocity.0, velocity.1) {
+                return Some((speed, angle));
             }
         }
         None
     }
 }
 
-pub fn total_ship_strength(ships: &[&Ship]) -> f64 {
-    total_strength(&ships.iter().map(|s| s.hp).collect::<Vec<i32>>().as_slice())
+// the velocity a thrust command gives
+fn thrust_velocity(speed: i32, angle: i32) -> Position {
+    Position::from_polar(f64::from(speed), Angle::degrees(f64::from(angle)))
 }
 
-pub fn total_strength(hps: &[i32]) -> f64 {
-    // TODO tune this
-    let per_ship_multiplier = 0.15;
+pub fn total_strength(hps: &[i32], configs: &Configs) -> f64 {
+    let per_ship_multiplier = configs.per_ship_multiplier;
     hps.iter()
         .map(|hp| *hp as f64 / MAX_SHIP_HEALTH as f64)
         .fold(0.0, |acc, s| acc + s) * ((1.0 - per_ship_multiplier) + (per_ship_multiplier * hps.len() as f64))
 }
 
-pub fn commitment(ship: &Ship, commitment_map: &HashMap<i32, Vec<i32>>) -> f64 {
-    total_strength(commitment_map.get(&ship.id).unwrap().as_slice())
+pub fn commitment(ship: &Ship, commitment_map: &HashMap<i32, Vec<i32>>, configs: &Configs) -> f64 {
+    total_strength(commitment_map.get(&ship.id).unwrap().as_slice(), configs)
 }
 
 // takes a percent x and moves it into the scale of (scale - 1.0)
@@ -461,41 +495,45 @@ fn scaled_to(scale: f64, x: f64) -> f64 {
     (x * (1.0 - scale)) + scale
 }
 
+// how much closer two ships can get in t turns. Velocities only ever come from thrusts, so neither moves faster than
+// MAX_SPEED, and ships further apart than this plus whatever range is being checked can be left out
+fn reach(t: f64) -> f64 {
+    2.0 * MAX_SPEED as f64 * t
+}
+
 fn check_collision(obstacle_1: &Obstacle, obstacle_2: &Obstacle) -> bool {
     match collision_times(obstacle_1, obstacle_2) {
-        Some((t1, t2)) => (0.0 <= t1 && t1 <= 1.0) || (0.0 <= t2 && t2 <= 1.0) || t1 < 0.0 && t2 > 1.0,
+        Some((t1, t2)) => (0.0..=1.0).contains(&t1) || (0.0..=1.0).contains(&t2) || t1 < 0.0 && t2 > 1.0,
         None => false,
     }
 }
 
 fn collision_times(obstacle_1: &Obstacle, obstacle_2: &Obstacle) -> Option<(f64, f64)> {
-    let ship_vx = obstacle_1.velocity_x;
-    let ob_vx = obstacle_2.velocity_x;
-    let ship_vy = obstacle_1.velocity_y;
-    let ob_vy = obstacle_2.velocity_y;
-
     let radius = obstacle_1.radius + obstacle_2.radius + FUDGE;
 
-    let Position(ship_px, ship_py) = obstacle_1.position;
-    let Position(ob_px, ob_py) = obstacle_2.position;
+    let offset = obstacle_1.position - obstacle_2.position;
+    let relative_velocity = Position(
+        obstacle_1.velocity_x - obstacle_2.velocity_x,
+        obstacle_1.velocity_y - obstacle_2.velocity_y,
+    );
 
-    let a = (ship_vx - ob_vx).powi(2) + (ship_vy - ob_vy).powi(2);
-    let b = 2.0 * (ship_px - ob_px) * (ship_vx - ob_vx) + 2.0 * (ship_py - ob_py) * (ship_vy - ob_vy);
-    let c = (ship_px - ob_px).powi(2) + (ship_py - ob_py).powi(2) - radius.powi(2);
+    let a = relative_velocity.length_squared();
+    let b = 2.0 * offset.dot(relative_velocity);
+    let c = offset.length_squared() - radius.powi(2);
 
     let discriminant = b.powi(2) - (4.0 * a * c);
 
     if discriminant < 0.0 {
         None
     } else {
-        let t1 = ((-1.0 * b) - discriminant.sqrt()) / (2.0 * a);
-        let t2 = ((-1.0 * b) + discriminant.sqrt()) / (2.0 * a);
+        let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+        let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
         Some((t1, t2))
     }
 }
 
 
-fn dock_value_helper<T: Entity>(entity: &T, planet: &Planet, game_map: &GameMap) -> f64 {
+fn dock_value_helper<T: Entity>(entity: &T, planet: &Planet, game_map: &GameMap, configs: &Configs) -> f64 {
     let planet_pos = planet.get_position();
     let edge_dist_x = if planet_pos.0 > game_map.width() / 2.0 {
         game_map.width() - planet_pos.0
@@ -508,16 +546,12 @@ fn dock_value_helper<T: Entity>(entity: &T, planet: &Planet, game_map: &GameMap)
         planet_pos.1
     };
     let edge_dist_modifier = scaled_to(
-        0.50,
-        ((((edge_dist_x / game_map.width()) + (edge_dist_y / game_map.height())))),
+        configs.edge_scale,
+        (edge_dist_x / game_map.width()) + (edge_dist_y / game_map.height()),
     );
 
     let size_factor = match planet.num_docking_spots {
-        2 => 1.30,
-        3 => 1.10,
-        4 => 1.10,
-        5 => 1.05,
-        6 => 1.00,
+        2..=6 => configs.size_factor(planet.num_docking_spots),
         _ => assert_unreachable!(),
     };
     let planet_total = planet.commitment() + planet.docked_ships.len() as i32;
@@ -532,7 +566,7 @@ fn dock_value_helper<T: Entity>(entity: &T, planet: &Planet, game_map: &GameMap)
     commitment_factor * size_factor
         * (entity.distance_to_surface(planet)
            // because docking will put the ship out of commision for that long. I guess?
-           + (2 * MAX_SPEED * (DOCK_TURNS + 0)) as f64) * edge_dist_modifier
+           + (2 * MAX_SPEED * DOCK_TURNS) as f64) * edge_dist_modifier
 }
 
 impl PartialEq for Ship {
@@ -542,24 +576,22 @@ impl PartialEq for Ship {
 }
 
 impl Decodable for Ship {
-    fn parse<'a, I>(tokens: &mut I) -> Ship
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let id = i32::parse(tokens);
+    fn parse(tokens: &mut Tokens) -> Result<Ship, ParseError> {
+        let id = i32::parse(tokens)?;
+        // filled in by Player::parse
         let owner_id = 0;
-        let positions = vec![Position::parse(tokens)];
-        let hp = i32::parse(tokens);
-        let velocity_x = Cell::new(f64::parse(tokens));
-        let velocity_y = Cell::new(f64::parse(tokens));
-        let docking_status = DockingStatus::parse(tokens);
-        let docked_planet_raw = i32::parse(tokens);
+        let positions = vec![Position::parse(tokens)?];
+        let hp = i32::parse(tokens)?;
+        let velocity_x = Cell::new(f64::parse(tokens)?);
+        let velocity_y = Cell::new(f64::parse(tokens)?);
+        let docking_status = DockingStatus::parse(tokens)?;
+        let docked_planet_raw = i32::parse(tokens)?;
         let docked_planet = match docking_status {
             DockingStatus::UNDOCKED => None,
             _ => Some(docked_planet_raw),
         };
-        let progress = i32::parse(tokens);
-        let cooldown = i32::parse(tokens);
+        let progress = i32::parse(tokens)?;
+        let cooldown = i32::parse(tokens)?;
         let command = Cell::new(None);
 
         let ship = Ship {
@@ -575,11 +607,25 @@ impl Decodable for Ship {
             cooldown,
             command,
         };
-        return ship;
+        Ok(ship)
     }
 }
 
-#[derive(PartialEq, Debug)]
+impl Encodable for Ship {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.id.encode(tokens);
+        self.get_position().encode(tokens);
+        self.hp.encode(tokens);
+        self.velocity_x.get().encode(tokens);
+        self.velocity_y.get().encode(tokens);
+        self.docking_status.encode(tokens);
+        self.docked_planet.unwrap_or(0).encode(tokens);
+        self.progress.encode(tokens);
+        self.cooldown.encode(tokens);
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct Planet {
     pub id: i32,
     pub position: Position,
@@ -595,6 +641,23 @@ pub struct Planet {
 }
 
 impl Planet {
+    // an unowned planet with nothing docked to it
+    pub fn new(id: i32, position: Position, hp: i32, radius: f64, num_docking_spots: i32, remaining_resources: i32) -> Planet {
+        Planet {
+            id,
+            position,
+            hp,
+            radius,
+            num_docking_spots,
+            current_production: 0,
+            remaining_resources,
+            owner: None,
+            docked_ships: vec![],
+            committed_ships: Cell::new(0),
+            doomed: Cell::new(false),
+        }
+    }
+
     pub fn is_owned(&self) -> bool {
         self.owner.is_some()
     }
@@ -605,7 +668,7 @@ impl Planet {
 
     #[allow(dead_code)]
     pub fn any_docked(&self) -> bool {
-        self.docked_ships.len() > 0
+        !self.docked_ships.is_empty()
     }
 
     pub fn get_obstacle(&self) -> Obstacle {
@@ -635,15 +698,15 @@ impl Planet {
     }
 
     pub fn turns_until_spawn(&self) -> i32 {
-        if self.docked_ships.len() == 0 {
+        if self.docked_ships.is_empty() {
             999999
         } else {
             (SHIP_COST - self.current_production) / (self.docked_ships.len() * 3) as i32
         }
     }
 
-    fn base_dock_value(&self, planet: &Planet, game_map: &GameMap) -> f64 {
-        dock_value_helper(self, planet, game_map)
+    fn base_dock_value(&self, planet: &Planet, game_map: &GameMap, configs: &Configs) -> f64 {
+        dock_value_helper(self, planet, game_map, configs)
     }
 
     fn explosion_radius(&self) -> f64 {
@@ -678,23 +741,20 @@ impl Planet {
 }
 
 impl Decodable for Planet {
-    fn parse<'a, I>(tokens: &mut I) -> Planet
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let id = i32::parse(tokens);
-        let position = Position::parse(tokens);
-        let hp = i32::parse(tokens);
-        let radius = f64::parse(tokens);
-        let num_docking_spots = i32::parse(tokens);
-        let current_production = i32::parse(tokens);
-        let remaining_resources = i32::parse(tokens);
-        let owner = Option::parse(tokens);
-        let docked_ships = Vec::parse(tokens);
+    fn parse(tokens: &mut Tokens) -> Result<Planet, ParseError> {
+        let id = i32::parse(tokens)?;
+        let position = Position::parse(tokens)?;
+        let hp = i32::parse(tokens)?;
+        let radius = f64::parse(tokens)?;
+        let num_docking_spots = i32::parse(tokens)?;
+        let current_production = i32::parse(tokens)?;
+        let remaining_resources = i32::parse(tokens)?;
+        let owner = Option::parse(tokens)?;
+        let docked_ships = Vec::parse(tokens)?;
         let committed_ships = Cell::new(0);
         let doomed = Cell::new(false);
 
-        return Planet {
+        Ok(Planet {
             id,
             position,
             hp,
@@ -706,25 +766,43 @@ impl Decodable for Planet {
             docked_ships,
             committed_ships,
             doomed,
-        };
+        })
+    }
+}
+
+impl Encodable for Planet {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.id.encode(tokens);
+        self.position.encode(tokens);
+        self.hp.encode(tokens);
+        self.radius.encode(tokens);
+        self.num_docking_spots.encode(tokens);
+        self.current_production.encode(tokens);
+        self.remaining_resources.encode(tokens);
+        self.owner.encode(tokens);
+        self.docked_ships.encode(tokens);
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct GameState {
     pub players: Vec<Player>,
     pub planets: Vec<Planet>,
 }
 
 impl Decodable for GameState {
-    fn parse<'a, I>(tokens: &mut I) -> GameState
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let players = Vec::parse(tokens);
-        let planets = Vec::parse(tokens);
+    fn parse(tokens: &mut Tokens) -> Result<GameState, ParseError> {
+        let players = Vec::parse(tokens)?;
+        let planets = Vec::parse(tokens)?;
 
-        return GameState { players, planets };
+        Ok(GameState { players, planets })
+    }
+}
+
+impl Encodable for GameState {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.players.encode(tokens);
+        self.planets.encode(tokens);
     }
 }
 
@@ -735,15 +813,11 @@ pub trait Entity: Sized {
 
     fn smart_distance_to<T: Entity>(&self, target: &T) -> f64 {
         // TODO: use pathfinding algo
-        let Position(x1, y1) = self.get_position();
-        let Position(x2, y2) = target.get_position();
-        (x2 - x1).powi(2) + (y2 - y1).powi(2)
+        self.distance_to_unsq(target)
     }
 
     fn distance_to_unsq<T: Entity>(&self, target: &T) -> f64 {
-        let Position(x1, y1) = self.get_position();
-        let Position(x2, y2) = target.get_position();
-        (x2 - x1).powi(2) + (y2 - y1).powi(2)
+        (target.get_position() - self.get_position()).length_squared()
     }
 
     fn distance_to<T: Entity>(&self, target: &T) -> f64 {
@@ -759,31 +833,20 @@ pub trait Entity: Sized {
     }
 
     fn dist_to_at<T: Entity>(&self, target: &T, t: f64) -> f64 {
-        let Position(x1, y1) = self.get_position_at(t);
-        let Position(x2, y2) = target.get_position_at(t);
-        f64::sqrt((x2 - x1).powi(2) + (y2 - y1).powi(2))
+        (target.get_position_at(t) - self.get_position_at(t)).length()
     }
 
     fn dist_to_at_less_than<T: Entity>(&self, target: &T, t: f64, query: f64) -> bool {
-        let Position(x1, y1) = self.get_position_at(t);
-        let Position(x2, y2) = target.get_position_at(t);
-        (x2 - x1).powi(2) + (y2 - y1).powi(2) < query.powi(2)
+        (target.get_position_at(t) - self.get_position_at(t)).length_squared() < query.powi(2)
     }
 
     fn calculate_angle_between<T: Entity>(&self, target: &T) -> f64 {
-        let Position(x1, y1) = self.get_position();
-        let Position(x2, y2) = target.get_position();
-        (f64::atan2(y2 - y1, x2 - x1).to_degrees() + 360.0) % 360.0
+        Angle::between(&self.get_position(), &target.get_position()).to_degrees()
     }
 
     fn closest_point_to<T: Entity>(&self, target: &T, min_distance: f64) -> Position {
-        let angle = target.calculate_angle_between(self);
-        let radius = target.get_radius() + min_distance;
-        let Position(target_x, target_y) = target.get_position();
-        let x = target_x + radius * f64::cos(angle.to_radians());
-        let y = target_y + radius * f64::sin(angle.to_radians());
-
-        Position(x, y)
+        let angle = Angle::between(&target.get_position(), &self.get_position());
+        target.get_position() + Position::from_polar(target.get_radius() + min_distance, angle)
     }
 
     fn nearest_entity<'a, T: 'a + Entity>(&self, entities: &'a [&T]) -> &'a T {
@@ -807,11 +870,7 @@ impl Entity for Ship {
     }
 
     fn get_position_at(&self, t: f64) -> Position {
-        let pos = self.get_position();
-        Position(
-            pos.0 + (t * self.velocity_x.get()),
-            pos.1 + (t * self.velocity_y.get()),
-        )
+        self.get_position() + Position(self.velocity_x.get(), self.velocity_y.get()) * t
     }
 
     fn get_radius(&self) -> f64 {
@@ -1,75 +1,128 @@
 
-use std::io::stdin;
-use hlt::parse::Decodable;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io;
+use hlt::parse::{Decodable, ParseError, Tokens};
 use hlt::entity::GameState;
-use hlt::command::Command;
+use hlt::command::{encode_commands, validate_commands, Command};
 use hlt::game_map::GameMap;
+use hlt::logging::Logger;
+use hlt::transport::{stdio, Streams, Transport};
 use time::PreciseTime;
 
-#[derive(Debug)]
 pub struct Game {
     pub my_id: usize,
     pub map_width: i32,
     pub map_height: i32,
+    transport: RefCell<Box<dyn Transport>>,
+    // set once the engine has closed the input
+    finished: Cell<bool>,
+}
+
+impl fmt::Debug for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Game")
+            .field("my_id", &self.my_id)
+            .field("map_width", &self.map_width)
+            .field("map_height", &self.map_height)
+            .finish()
+    }
 }
 
 impl Game {
-    fn read_line() -> String {
-        let mut buffer = String::new();
-        stdin().read_line(&mut buffer).expect("Read error");
-        return buffer;
+    fn read_line(&self) -> String {
+        let line = self.transport.borrow_mut().read_line().expect("Read error");
+        if line.is_empty() {
+            self.finished.set(true);
+        }
+        line
     }
 
-    fn read_id() -> usize {
-        let line = Game::read_line();
-        let parts = line.split_whitespace();
-        let mut iter = parts.into_iter();
-        return usize::parse(&mut iter);
+    // there is nothing sensible to fall back to if the handshake is garbled
+    fn read_id(&self) -> usize {
+        let line = self.read_line();
+        let mut tokens = Tokens::new(&line);
+        usize::parse(&mut tokens).unwrap_or_else(|e| panic!("Could not read player id: {}", e))
     }
 
-    fn read_size() -> (i32, i32) {
-        let line = Game::read_line();
-        let parts = line.split_whitespace();
-        let mut iter = parts.into_iter();
-        let width = i32::parse(&mut iter);
-        let height = i32::parse(&mut iter);
-        return (width, height);
+    fn read_size(&self) -> (i32, i32) {
+        let line = self.read_line();
+        let mut tokens = Tokens::new(&line);
+        let mut parse = || i32::parse(&mut tokens).unwrap_or_else(|e| panic!("Could not read map size: {}", e));
+        let width = parse();
+        let height = parse();
+        (width, height)
     }
 
     pub fn new(name: &str) -> Game {
-        let my_id = Game::read_id();
-        let (map_width, map_height) = Game::read_size();
+        Game::with_transport(name, Box::new(stdio()))
+    }
 
-        println!("{}", name);
+    // plays over any transport, e.g. scripted map lines in tests or pipes to a local engine
+    pub fn with_transport(name: &str, transport: Box<dyn Transport>) -> Game {
+        let mut game = Game {
+            my_id: 0,
+            map_width: 0,
+            map_height: 0,
+            transport: RefCell::new(transport),
+            finished: Cell::new(false),
+        };
+        game.my_id = game.read_id();
+        let (map_width, map_height) = game.read_size();
+        game.map_width = map_width;
+        game.map_height = map_height;
+
+        game.write_line(name);
+
+        // the initial map only has to be read past, the first turn sends it again
+        let _ = game.create_map();
+        game
+    }
 
-        let game = Game {
+    // a game with no engine on the other end, for building maps that come from somewhere else
+    pub fn offline(my_id: usize, map_width: i32, map_height: i32) -> Game {
+        Game {
             my_id,
             map_width,
             map_height,
-        };
-        game.create_map();
-        game
+            transport: RefCell::new(Box::new(Streams::new(io::empty(), io::sink()))),
+            finished: Cell::new(true),
+        }
+    }
+
+    // whether the engine has closed the input, after which there are no more turns to play
+    pub fn finished(&self) -> bool {
+        self.finished.get()
     }
 
-    pub fn create_map(&self) -> GameMap {
-        let line = Game::read_line();
-        let parts = line.split_whitespace();
-        let mut iter = parts.into_iter();
-        let game_state = GameState::parse(&mut iter);
-        return GameMap::new(self, game_state);
+    fn write_line(&self, line: &str) {
+        self.transport.borrow_mut().write_line(line).expect("Write error");
     }
 
-    pub fn update_map(&self, previous_map: GameMap) -> (GameMap, PreciseTime) {
-        let line = Game::read_line();
+    pub fn create_map(&self) -> Result<GameMap<'_>, ParseError> {
+        let line = self.read_line();
+        let game_state = GameState::parse(&mut Tokens::new(&line))?;
+        Ok(GameMap::new(self, game_state))
+    }
+
+    // reads the next turn's map. previous_map is left alone, so if the line can't be parsed the caller can carry
+    // on from it next turn
+    pub fn update_map(&self, previous_map: &GameMap) -> Result<(GameMap<'_>, PreciseTime), ParseError> {
+        let line = self.read_line();
         let start_time = PreciseTime::now();
-        let parts = line.split_whitespace();
-        let mut iter = parts.into_iter();
-        let mut game_state = GameState::parse(&mut iter);
-        if previous_map.state.players.len() > 0 {
+        let mut game_state = GameState::parse(&mut Tokens::new(&line))?;
+        Game::carry_over_positions(&previous_map.state, &mut game_state);
+        Ok((GameMap::new(self, game_state), start_time))
+    }
+
+    // ships are only told where they are now, so each one's position history is carried over from the previous
+    // turn's state
+    pub fn carry_over_positions(previous_state: &GameState, game_state: &mut GameState) {
+        if !previous_state.players.is_empty() {
             for player in game_state.players.iter_mut() {
-                let previous_ships = previous_map.state.players[player.id as usize].all_ships();
+                let previous_ships = previous_state.players[player.id as usize].all_ships();
                 player.strength = player.ships.len() as f64;
-                for mut ship in player.ships.iter_mut() {
+                for ship in player.ships.iter_mut() {
                     ship.owner_id = player.id;
                     let previous_ship = previous_ships.iter().find(|s| s.id == ship.id);
                     match previous_ship {
@@ -86,13 +139,14 @@ impl Game {
                 }
             }
         }
-        return (GameMap::new(self, game_state), start_time);
     }
 
-    pub fn send_command_queue(&self, commands: Vec<Command>) {
-        for command in commands {
-            print!("{}", command.encode());
+    // sends what validate_commands lets through and logs the rest
+    pub fn send_command_queue(&self, game_map: &GameMap, commands: Vec<Command>, logger: &mut Logger) {
+        let (accepted, rejected) = validate_commands(&commands, game_map);
+        for (command, rejection) in rejected {
+            logger.log(&format!("  rejected {:?}: {}", command, rejection));
         }
-        println!();
+        self.write_line(&encode_commands(&accepted));
     }
 }
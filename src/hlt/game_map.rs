@@ -1,18 +1,113 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 use hlt::game::Game;
 use hlt::entity::{Entity, GameState, Obstacle, Planet, Position, Ship};
 use hlt::player::Player;
 use hlt::collision::intersect_segment_circle;
 use hlt::constants::SHIP_RADIUS;
+use hlt::pathfind::{clear_to_waypoint, first_waypoint};
+use hlt::spatial::SpatialIndex;
 
 pub struct GameMap<'a> {
     game: &'a Game,
-    pub state: GameState,
+    // shared with the views of the same turn from other players' side, see as_player
+    pub state: Rc<GameState>,
+    // where everything in state is, and which ships are whose, both worked out along with the map and so never
+    // out of date, state being shared and so never changed
+    index: Rc<SpatialIndex>,
+    lookup: Lookup,
+    // first waypoints by the cell of the start and the destination
+    paths: RefCell<HashMap<PathKey, Option<Position>>>,
+}
+
+// a start and destination as (start cell x, start cell y, destination x bits, destination y bits)
+type PathKey = (i64, i64, u64, u64);
+
+// size of the squares ships starting in which share the way to a destination
+const PATH_CELL: f64 = 2.0;
+
+// a ship as (index into GameState::players, index into that player's ships)
+type ShipKey = (usize, usize);
+
+// Ships by id and sorted into the groups the bot asks for over and over, all in state order. Docked here means
+// anything but undocked, i.e. docking and undocking ships too, which can't move any more than docked ones can
+struct Lookup {
+    ships_by_id: HashMap<i32, ShipKey>,
+    planets_by_id: HashMap<i32, usize>,
+    all: Vec<ShipKey>,
+    mine: Vec<ShipKey>,
+    enemy: Vec<ShipKey>,
+    my_docked: Vec<ShipKey>,
+    my_undocked: Vec<ShipKey>,
+    enemy_docked: Vec<ShipKey>,
+    enemy_undocked: Vec<ShipKey>,
+}
+
+impl Lookup {
+    fn new(state: &GameState, my_id: i32) -> Lookup {
+        let mut lookup = Lookup {
+            ships_by_id: HashMap::new(),
+            planets_by_id: state.planets.iter().enumerate().map(|(p, planet)| (planet.id, p)).collect(),
+            all: vec![],
+            mine: vec![],
+            enemy: vec![],
+            my_docked: vec![],
+            my_undocked: vec![],
+            enemy_docked: vec![],
+            enemy_undocked: vec![],
+        };
+        for (p, player) in state.players.iter().enumerate() {
+            for (s, ship) in player.ships.iter().enumerate() {
+                let key = (p, s);
+                lookup.ships_by_id.insert(ship.id, key);
+                lookup.all.push(key);
+                let (owned, docked) = if player.id == my_id {
+                    (&mut lookup.mine, &mut lookup.my_docked)
+                } else {
+                    (&mut lookup.enemy, &mut lookup.enemy_docked)
+                };
+                owned.push(key);
+                if !ship.is_undocked() {
+                    docked.push(key);
+                } else if player.id == my_id {
+                    lookup.my_undocked.push(key);
+                } else {
+                    lookup.enemy_undocked.push(key);
+                }
+            }
+        }
+        lookup
+    }
 }
 
 impl<'a> GameMap<'a> {
-    pub fn new(game: &Game, state: GameState) -> GameMap {
-        return GameMap { game, state };
+    pub fn new(game: &Game, state: GameState) -> GameMap<'_> {
+        let index = SpatialIndex::new(&state, game.map_width as f64, game.map_height as f64);
+        let lookup = Lookup::new(&state, game.my_id as i32);
+        GameMap {
+            game,
+            state: Rc::new(state),
+            index: Rc::new(index),
+            lookup,
+            paths: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // the same turn seen from the side of the player game is for, sharing everything but which ships are whose
+    pub fn as_player<'b>(&self, game: &'b Game) -> GameMap<'b> {
+        GameMap {
+            game,
+            state: Rc::clone(&self.state),
+            index: Rc::clone(&self.index),
+            lookup: Lookup::new(&self.state, game.my_id as i32),
+            paths: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn ships(&self, keys: &[ShipKey]) -> Vec<&Ship> {
+        keys.iter().map(|&(p, s)| &self.state.players[p].ships[s]).collect()
     }
 
     pub fn all_planets(&self) -> &Vec<Planet> {
@@ -33,6 +128,10 @@ impl<'a> GameMap<'a> {
             .collect()
     }
 
+    pub fn get_planet(&self, planet_id: i32) -> Option<&Planet> {
+        self.lookup.planets_by_id.get(&planet_id).map(|&p| &self.state.planets[p])
+    }
+
     fn my_ship_obstacles(&self, excluded_ship: &Ship) -> Vec<Obstacle> {
         self.my_ships()
             .into_iter()
@@ -42,17 +141,15 @@ impl<'a> GameMap<'a> {
     }
 
     fn enemy_docked_ship_obstacles(&self) -> Vec<Obstacle> {
-        self.enemy_ships()
+        self.enemy_docked_ships()
             .into_iter()
-            .filter(|s| !s.is_undocked())
             .map(|s| s.get_obstacle())
             .collect::<Vec<Obstacle>>()
     }
 
     fn enemy_undocked_ship_danger_obstacles(&self) -> Vec<Obstacle> {
-        self.enemy_ships()
+        self.enemy_undocked_ships()
             .into_iter()
-            .filter(|s| s.is_undocked())
             .map(|s| s.get_danger_obstacle())
             .collect::<Vec<Obstacle>>()
     }
@@ -81,7 +178,7 @@ impl<'a> GameMap<'a> {
         obstacles.append(&mut self.my_ship_obstacles(raiding_ship));
         obstacles.append(&mut self.enemy_docked_ship_obstacles());
         let defender_ids: Vec<i32> = target_ship.defenders(self).iter().map(|s| s.id).collect();
-        for enemy_ship in self.enemy_ships().into_iter().filter(|s| s.is_undocked()) {
+        for enemy_ship in self.enemy_undocked_ships() {
             if defender_ids.contains(&enemy_ship.id) {
                 obstacles.push(enemy_ship.get_obstacle())
             } else {
@@ -95,9 +192,8 @@ impl<'a> GameMap<'a> {
         let mut obstacles: Vec<Obstacle> = vec![];
         obstacles.append(&mut self.all_planet_obstacles());
         obstacles.append(&mut self.my_ship_obstacles(raiding_ship));
-        obstacles.append(&mut self.enemy_ships()
+        obstacles.append(&mut self.enemy_undocked_ships()
             .into_iter()
-            .filter(|s| s.is_undocked())
             .map(|s| s.get_obstacle())
             .collect::<Vec<Obstacle>>());
         obstacles
@@ -107,31 +203,13 @@ impl<'a> GameMap<'a> {
         self.obstacles_for_dock(fleeing_ship)
     }
 
-    pub fn obstacles_for_eradicate(&self, ship: &Ship, player_id: i32) -> Vec<Obstacle> {
-        let mut obstacles: Vec<Obstacle> = vec![];
-        obstacles.append(&mut self.all_planet_obstacles());
-        obstacles.append(&mut self.my_ship_obstacles(ship));
-        obstacles.append(&mut self.enemy_ships()
-            .into_iter()
-            .filter(|s| s.owner_id != player_id && !s.is_undocked())
-            .map(|s| s.get_obstacle())
-            .collect::<Vec<Obstacle>>());
-        obstacles.append(&mut self.enemy_ships()
-            .into_iter()
-            .filter(|s| s.owner_id != player_id && s.is_undocked())
-            .map(|s| s.get_danger_obstacle())
-            .collect::<Vec<Obstacle>>());
-        obstacles
-    }
-
     pub fn obstacles_for_defend(&self, defending_ship: &Ship) -> Vec<Obstacle> {
         let mut obstacles: Vec<Obstacle> = vec![];
         obstacles.append(&mut self.all_planet_obstacles());
         obstacles.append(&mut self.my_ship_obstacles(defending_ship));
         obstacles.append(&mut self.enemy_docked_ship_obstacles());
-        obstacles.append(&mut self.enemy_ships()
+        obstacles.append(&mut self.enemy_undocked_ships()
             .into_iter()
-            .filter(|s| s.is_undocked())
         // by including this, the ship will not try to avoid combat but will try to avoid running
         // into the enemy. TODO could omit altogether to allow collisions
             .map(|s| s.get_obstacle())
@@ -144,39 +222,82 @@ impl<'a> GameMap<'a> {
     }
 
     pub fn all_ships(&self) -> Vec<&Ship> {
-        self.state
-            .players
-            .iter()
-            .flat_map(|p| p.all_ships())
-            .collect()
+        self.ships(&self.lookup.all)
     }
 
     pub fn enemy_ships(&self) -> Vec<&Ship> {
-        self.state
-            .players
-            .iter()
-            .filter(|p| p.id != self.game.my_id as i32)
-            .flat_map(|p| p.all_ships())
-            .collect()
+        self.ships(&self.lookup.enemy)
     }
 
     pub fn my_ships(&self) -> Vec<&Ship> {
-        self.state
-            .players
-            .iter()
-            .filter(|p| p.id == self.game.my_id as i32)
-            .flat_map(|p| p.all_ships())
+        self.ships(&self.lookup.mine)
+    }
+
+    pub fn my_docked_ships(&self) -> Vec<&Ship> {
+        self.ships(&self.lookup.my_docked)
+    }
+
+    pub fn my_undocked_ships(&self) -> Vec<&Ship> {
+        self.ships(&self.lookup.my_undocked)
+    }
+
+    pub fn enemy_docked_ships(&self) -> Vec<&Ship> {
+        self.ships(&self.lookup.enemy_docked)
+    }
+
+    pub fn enemy_undocked_ships(&self) -> Vec<&Ship> {
+        self.ships(&self.lookup.enemy_undocked)
+    }
+
+    // every ship with its center within radius of position, in the order all_ships gives them
+    pub fn ships_within(&self, position: &Position, radius: f64) -> Vec<&Ship> {
+        self.index
+            .ships_near(position, radius)
+            .into_iter()
+            .map(|(p, s)| &self.state.players[p].ships[s])
+            .filter(|s| s.get_position().distance_to(position) <= radius)
             .collect()
     }
 
+    // the ship closest to position of those passing the filter, searching outwards until one turns up
+    pub fn nearest_ship<F: Fn(&Ship) -> bool>(&self, position: &Position, filter: F) -> Option<&Ship> {
+        let mut radius = SHIP_RADIUS;
+        while radius < 2.0 * self.index.extent() {
+            radius *= 2.0;
+            let nearest = self.ships_within(position, radius)
+                .into_iter()
+                .filter(|s| filter(s))
+                .min_by(|a, b| {
+                    a.distance_to(position)
+                        .partial_cmp(&b.distance_to(position))
+                        .unwrap()
+                });
+            if nearest.is_some() {
+                return nearest;
+            }
+        }
+        // nothing on the map passes, but look everywhere in case something is well off it
+        self.all_ships()
+            .into_iter()
+            .filter(|s| filter(s))
+            .min_by(|a, b| {
+                a.distance_to(position)
+                    .partial_cmp(&b.distance_to(position))
+                    .unwrap()
+            })
+    }
+
     pub fn get_me(&self) -> &Player {
         let my_id = self.game.my_id;
-        let player = &self.state.players[my_id];
-        return player;
+        &self.state.players[my_id]
     }
 
     pub fn get_ship(&self, ship_id: i32) -> &Ship {
-        self.all_ships().iter().find(|s| s.id == ship_id).unwrap()
+        self.find_ship(ship_id).unwrap()
+    }
+
+    pub fn find_ship(&self, ship_id: i32) -> Option<&Ship> {
+        self.lookup.ships_by_id.get(&ship_id).map(|&(p, s)| &self.state.players[p].ships[s])
     }
 
     pub fn closest_stationary_obstacle(
@@ -185,27 +306,52 @@ impl<'a> GameMap<'a> {
         destination: &Position,
         fudge: f64,
     ) -> Option<Obstacle> {
-        // let mut logger = Logger::new(0);
-        let mut dist: f64 = 99999999f64;
-        let mut obstacle: Option<Obstacle> = None;
-        for planet in self.all_planets() {
-            let distance_to_surface = planet.distance_to(start) - (SHIP_RADIUS + planet.get_radius() + fudge);
-            if distance_to_surface < dist && intersect_segment_circle(start, destination, planet, fudge + SHIP_RADIUS) {
-                dist = distance_to_surface;
-                obstacle = Some(planet.get_obstacle());
-            }
-        }
+        self.stationary_obstacles_between(start, destination, fudge)
+            .into_iter()
+            .min_by(|o1, o2| {
+                (o1.position.distance_to(start) - o1.radius)
+                    .partial_cmp(&(o2.position.distance_to(start) - o2.radius))
+                    .unwrap()
+            })
+    }
+
+    // every planet and every ship which is not undocked that a ship flying from start to destination would hit
+    pub fn stationary_obstacles_between(&self, start: &Position, destination: &Position, fudge: f64) -> Vec<Obstacle> {
+        // only things around the segment can be in the way of it
+        let middle = (*start + *destination) * 0.5;
+        let reach = start.distance_to(destination) / 2.0 + SHIP_RADIUS + fudge;
+        let planets = self.index
+            .planets_near(&middle, reach)
+            .into_iter()
+            .map(|p| &self.state.planets[p])
+            .filter(|planet| intersect_segment_circle(start, destination, *planet, fudge + SHIP_RADIUS))
+            .map(|planet| planet.get_obstacle());
         // all ships which are not undocked are also stationary obstacles
-        for other_ship in self.all_ships().iter().filter(|s| !s.is_undocked()) {
-            let distance_to_surface = other_ship.distance_to(start) - (SHIP_RADIUS + other_ship.get_radius() + fudge);
-            if distance_to_surface < dist
-                && intersect_segment_circle(start, destination, *other_ship, fudge + SHIP_RADIUS)
-            {
-                dist = distance_to_surface;
-                obstacle = Some(other_ship.get_obstacle());
-            }
+        let ships = self.ships_within(&middle, reach + SHIP_RADIUS)
+            .into_iter()
+            .filter(|s| !s.is_undocked() && intersect_segment_circle(start, destination, *s, fudge + SHIP_RADIUS))
+            .map(|s| s.get_obstacle());
+        planets.chain(ships).collect()
+    }
+
+    // where a ship at start should head first to get to destination around everything stationary, see
+    // pathfind::first_waypoint. Ships starting close together going to the same place share the waypoint worked
+    // out for the first of them, as long as the way to it is clear from where they are too
+    pub fn waypoint(&self, start: &Position, destination: &Position) -> Option<Position> {
+        let key = (
+            (start.0 / PATH_CELL).floor() as i64,
+            (start.1 / PATH_CELL).floor() as i64,
+            destination.0.to_bits(),
+            destination.1.to_bits(),
+        );
+        match self.paths.borrow().get(&key) {
+            Some(&None) => return None,
+            Some(&Some(waypoint)) if clear_to_waypoint(start, &waypoint, destination, self) => return Some(waypoint),
+            _ => {}
         }
-        obstacle
+        let waypoint = first_waypoint(start, destination, self);
+        self.paths.borrow_mut().insert(key, waypoint);
+        waypoint
     }
 
     pub fn width(&self) -> f64 {
@@ -217,9 +363,62 @@ impl<'a> GameMap<'a> {
     }
 
     pub fn center(&self) -> Position {
-        Position {
-            0: self.width() / 2.0,
-            1: self.height() / 2.0,
-        }
+        Position(self.width() / 2.0, self.height() / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::entity::{DockingStatus, GameState, Planet, Position, Ship};
+    use hlt::game::Game;
+    use hlt::game_map::GameMap;
+    use hlt::player::Player;
+
+    #[test]
+    fn test_lookup() {
+        let game = Game::offline(1, 240, 160);
+        let ships: Vec<Ship> = (0..6).map(|id| Ship::new(id, id % 2, Position(10.0 * id as f64, 10.0))).collect();
+        let state = GameState {
+            players: (0..2)
+                .map(|id| Player {
+                    id,
+                    ships: ships
+                        .iter()
+                        .filter(|s| s.owner_id == id)
+                        .cloned()
+                        .map(|mut s| {
+                            if s.id >= 4 {
+                                s.docking_status = DockingStatus::DOCKING;
+                            }
+                            s
+                        })
+                        .collect(),
+                    strength: 0.0,
+                })
+                .collect(),
+            planets: vec![Planet::new(7, Position(50.0, 50.0), 1000, 3.0, 3, 1000)],
+        };
+        let game_map = GameMap::new(&game, state);
+        let ids = |ships: Vec<&Ship>| ships.iter().map(|s| s.id).collect::<Vec<i32>>();
+        assert_eq!(vec![0, 2, 4, 1, 3, 5], ids(game_map.all_ships()));
+        assert_eq!(vec![1, 3, 5], ids(game_map.my_ships()));
+        assert_eq!(vec![0, 2, 4], ids(game_map.enemy_ships()));
+        assert_eq!(vec![5], ids(game_map.my_docked_ships()));
+        assert_eq!(vec![1, 3], ids(game_map.my_undocked_ships()));
+        assert_eq!(vec![4], ids(game_map.enemy_docked_ships()));
+        assert_eq!(vec![0, 2], ids(game_map.enemy_undocked_ships()));
+        assert_eq!(Position(30.0, 10.0), game_map.get_ship(3).positions[0]);
+        assert!(game_map.find_ship(6).is_none());
+        assert_eq!(7, game_map.get_planet(7).unwrap().id);
+        assert!(game_map.get_planet(0).is_none());
+
+        let their_game = Game::offline(0, 240, 160);
+        let their_map = game_map.as_player(&their_game);
+        assert_eq!(vec![0, 2, 4], ids(their_map.my_ships()));
+        assert_eq!(vec![1, 3, 5], ids(their_map.enemy_ships()));
+        assert_eq!(vec![4], ids(their_map.my_docked_ships()));
+        let mut near = ids(their_map.ships_within(&Position(20.0, 10.0), 15.0));
+        near.sort();
+        assert_eq!(vec![1, 2, 3], near);
     }
 }
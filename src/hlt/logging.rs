@@ -1,25 +1,35 @@
-use std::fs::File;
-use std::io::Write;
+use std::io::{self, Write};
 use std::fs::OpenOptions;
+use std::sync::{Arc, Mutex};
 
-pub struct Logger(File);
+// clones write to the same file, so the play loop and the strategy can share one log
+#[derive(Clone)]
+pub struct Logger(Arc<Mutex<Box<dyn Write + Send>>>);
 
 impl Logger {
     pub fn new(user_id: usize) -> Logger {
+        Logger::with_path(&format!("log_{}.txt", user_id))
+    }
+
+    pub fn with_path(path: &str) -> Logger {
         let file = OpenOptions::new()
             .append(true)
             .create(true)
-            .open(format!("log_{}.txt", user_id))
+            .open(path)
             .expect("Couldn't open file for logging!");
-        Logger(file)
+        Logger(Arc::new(Mutex::new(Box::new(file))))
+    }
+
+    // for bots run inside another program, which would otherwise all write to the same log file
+    pub fn discard() -> Logger {
+        Logger(Arc::new(Mutex::new(Box::new(io::sink()))))
     }
 
     pub fn log(&mut self, message: &str) -> bool {
-        self.0
-            .write(message.as_bytes())
+        let mut out = self.0.lock().unwrap();
+        out.write_all(message.as_bytes())
             .expect("Couldn't write to log!");
-        self.0
-            .write("\n".as_bytes())
+        out.write_all("\n".as_bytes())
             .expect("Couldn't write to log!");
         true
     }
@@ -1,12 +1,18 @@
 #[macro_use]
 pub mod macros;
+pub mod angle;
 pub mod entity;
 pub mod game;
 pub mod logging;
 pub mod command;
+pub mod config;
 pub mod constants;
 pub mod game_map;
 pub mod player;
 pub mod collision;
+pub mod combat;
 pub mod pathfind;
-mod parse;
+pub mod parse;
+pub mod spatial;
+pub mod transport;
+pub mod vector;
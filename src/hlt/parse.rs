@@ -1,94 +1,340 @@
+use std::error::Error;
+use std::fmt;
+use std::str::{FromStr, SplitWhitespace};
 
-use std::str::FromStr;
-use std::fmt::Debug;
+// why a line from the engine could not be parsed: the index of the offending token in the line, what should have
+// been there and what actually was, None meaning the line ended early
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub index: usize,
+    pub expected: &'static str,
+    pub found: Option<String>,
+}
 
-pub trait Decodable {
-    fn parse<'a, I>(&mut I) -> Self
-    where
-        I: Iterator<Item = &'a str>;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.found {
+            Some(ref found) => write!(f, "token {}: expected {}, got {:?}", self.index, self.expected, found),
+            None => write!(f, "token {}: expected {}, got end of line", self.index, self.expected),
+        }
+    }
 }
 
-fn parse_next_primitive<'a, T: FromStr, I>(tokens: &mut I, type_name: &str) -> T
-where
-    I: Iterator<Item = &'a str>,
-    <T as FromStr>::Err: Debug,
-{
-    let next_token = &tokens.next().expect(&"Expected token, got EOF");
-    let value: T = next_token.parse().expect(&format!(
-        "Could not parse {:?} as expected type {:?}",
-        &next_token,
-        type_name
-    ));
-    return value;
+impl Error for ParseError {}
+
+// the whitespace separated tokens of a line, counted as they are read
+pub struct Tokens<'a> {
+    tokens: SplitWhitespace<'a>,
+    index: usize,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(line: &'a str) -> Tokens<'a> {
+        Tokens {
+            tokens: line.split_whitespace(),
+            index: 0,
+        }
+    }
+
+    // the next token and its index, or an error naming what was expected if the line has run out
+    pub fn next(&mut self, expected: &'static str) -> Result<(usize, &'a str), ParseError> {
+        let index = self.index;
+        match self.tokens.next() {
+            Some(token) => {
+                self.index += 1;
+                Ok((index, token))
+            }
+            None => Err(ParseError {
+                index,
+                expected,
+                found: None,
+            }),
+        }
+    }
+}
+
+pub trait Decodable: Sized {
+    fn parse(tokens: &mut Tokens) -> Result<Self, ParseError>;
+}
+
+fn parse_next_primitive<T: FromStr>(tokens: &mut Tokens, type_name: &'static str) -> Result<T, ParseError> {
+    let (index, next_token) = tokens.next(type_name)?;
+    next_token.parse().map_err(|_| ParseError {
+        index,
+        expected: type_name,
+        found: Some(next_token.to_string()),
+    })
 }
 
 impl Decodable for f64 {
-    fn parse<'a, I>(tokens: &mut I) -> f64
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        return parse_next_primitive(tokens, "f64");
+    fn parse(tokens: &mut Tokens) -> Result<f64, ParseError> {
+        parse_next_primitive(tokens, "f64")
     }
 }
 
 impl Decodable for i32 {
-    fn parse<'a, I>(tokens: &mut I) -> i32
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        return parse_next_primitive(tokens, "i32");
+    fn parse(tokens: &mut Tokens) -> Result<i32, ParseError> {
+        parse_next_primitive(tokens, "i32")
     }
 }
 
 impl Decodable for usize {
-    fn parse<'a, I>(tokens: &mut I) -> usize
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        return parse_next_primitive(tokens, "usize");
+    fn parse(tokens: &mut Tokens) -> Result<usize, ParseError> {
+        parse_next_primitive(tokens, "usize")
     }
 }
 
 impl Decodable for bool {
-    fn parse<'a, I>(tokens: &mut I) -> bool
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let p = parse_next_primitive(tokens, "bool");
-        return match p {
-            0 => false,
-            1 => true,
-            _ => panic!(format!("Expected 0 or 1, got {:?}", p)),
-        };
+    fn parse(tokens: &mut Tokens) -> Result<bool, ParseError> {
+        let (index, token) = tokens.next("0 or 1")?;
+        match token {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            _ => Err(ParseError {
+                index,
+                expected: "0 or 1",
+                found: Some(token.to_string()),
+            }),
+        }
     }
 }
 
 impl<T: Decodable> Decodable for Vec<T> {
-    fn parse<'a, I>(tokens: &mut I) -> Vec<T>
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let size = parse_next_primitive(tokens, "i64");
-        let mut result = Vec::with_capacity(size);
+    fn parse(tokens: &mut Tokens) -> Result<Vec<T>, ParseError> {
+        let size: usize = parse_next_primitive(tokens, "usize")?;
+        // not allocated up front, a garbled size would ask for more memory than there is before the line runs out
+        let mut result = vec![];
         for _ in 0..size {
-            let p = T::parse(tokens);
-            result.push(p);
+            result.push(T::parse(tokens)?);
         }
-        return result;
+        Ok(result)
     }
 }
 
 impl<T: Decodable> Decodable for Option<T> {
-    fn parse<'a, I>(tokens: &mut I) -> Option<T>
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let present = bool::parse(tokens);
-        let value = T::parse(tokens);
-
-        return match present {
+    fn parse(tokens: &mut Tokens) -> Result<Option<T>, ParseError> {
+        let present = bool::parse(tokens)?;
+        let value = T::parse(tokens)?;
+
+        Ok(match present {
             true => Some(value),
             false => None,
-        };
+        })
+    }
+}
+
+// the other direction: writes values out in exactly the format Decodable reads, one token per push
+pub trait Encodable {
+    fn encode(&self, tokens: &mut Vec<String>);
+
+    // the value as a line the engine could have sent
+    fn to_wire(&self) -> String {
+        let mut tokens = vec![];
+        self.encode(&mut tokens);
+        tokens.join(" ")
+    }
+}
+
+impl Encodable for f64 {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        // Display prints the shortest string which parses back to the same value
+        tokens.push(self.to_string());
+    }
+}
+
+impl Encodable for i32 {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        tokens.push(self.to_string());
+    }
+}
+
+impl Encodable for usize {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        tokens.push(self.to_string());
+    }
+}
+
+impl Encodable for bool {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        tokens.push(if *self { "1" } else { "0" }.to_string());
+    }
+}
+
+impl<T: Encodable> Encodable for Vec<T> {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.len().encode(tokens);
+        for value in self.iter() {
+            value.encode(tokens);
+        }
+    }
+}
+
+// the engine always sends a value after the presence flag, a default one when there is nothing there
+impl<T: Encodable + Default> Encodable for Option<T> {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.is_some().encode(tokens);
+        match *self {
+            Some(ref value) => value.encode(tokens),
+            None => T::default().encode(tokens),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::fmt::Debug;
+
+    use proptest::prelude::*;
+
+    use hlt::entity::{DockingStatus, GameState, Planet, Position, Ship};
+    use hlt::parse::{Decodable, Encodable, ParseError, Tokens};
+    use hlt::player::Player;
+
+    fn round_trip<T: Decodable + Encodable + Debug>(value: &T) {
+        let line = value.to_wire();
+        let parsed = T::parse(&mut Tokens::new(&line)).unwrap();
+        // compared through Debug since ships are only equal by id
+        assert_eq!(format!("{:?}", value), format!("{:?}", parsed));
+        assert_eq!(line, parsed.to_wire());
+    }
+
+    fn coordinate() -> BoxedStrategy<f64> {
+        (prop::num::f64::NORMAL | prop::num::f64::ZERO).boxed()
+    }
+
+    fn position() -> impl Strategy<Value = Position> {
+        (coordinate(), coordinate()).prop_map(|(x, y)| Position(x, y))
+    }
+
+    fn docking_status() -> impl Strategy<Value = DockingStatus> {
+        prop_oneof![
+            Just(DockingStatus::UNDOCKED),
+            Just(DockingStatus::DOCKING),
+            Just(DockingStatus::DOCKED),
+            Just(DockingStatus::UNDOCKING),
+        ]
+    }
+
+    // only what the engine sends: a parsed ship has no owner, history or command yet
+    fn ship() -> impl Strategy<Value = Ship> {
+        (
+            any::<i32>(),
+            position(),
+            any::<i32>(),
+            (coordinate(), coordinate()),
+            docking_status(),
+            any::<i32>(),
+            (any::<i32>(), any::<i32>()),
+        ).prop_map(|(id, position, hp, velocity, docking_status, planet, (progress, cooldown))| {
+            let mut ship = Ship::new(id, 0, position);
+            ship.hp = hp;
+            ship.velocity_x = Cell::new(velocity.0);
+            ship.velocity_y = Cell::new(velocity.1);
+            ship.docking_status = docking_status;
+            ship.docked_planet = match docking_status {
+                DockingStatus::UNDOCKED => None,
+                _ => Some(planet),
+            };
+            ship.progress = progress;
+            ship.cooldown = cooldown;
+            ship
+        })
+    }
+
+    fn planet() -> impl Strategy<Value = Planet> {
+        (
+            any::<i32>(),
+            position(),
+            (any::<i32>(), coordinate()),
+            (any::<i32>(), any::<i32>(), any::<i32>()),
+            any::<Option<i32>>(),
+            prop::collection::vec(any::<i32>(), 0..6),
+        ).prop_map(|(id, position, (hp, radius), (spots, production, resources), owner, docked_ships)| {
+            let mut planet = Planet::new(id, position, hp, radius, spots, resources);
+            planet.current_production = production;
+            planet.owner = owner;
+            planet.docked_ships = docked_ships;
+            planet
+        })
+    }
+
+    fn player() -> impl Strategy<Value = Player> {
+        (any::<i32>(), prop::collection::vec(ship(), 0..4)).prop_map(|(id, ships)| Player {
+            id,
+            // a parsed player's ships are owned by it
+            ships: ships
+                .into_iter()
+                .map(|mut ship| {
+                    ship.owner_id = id;
+                    ship
+                })
+                .collect(),
+            strength: 0.0,
+        })
+    }
+
+    fn game_state() -> impl Strategy<Value = GameState> {
+        (prop::collection::vec(player(), 0..4), prop::collection::vec(planet(), 0..4))
+            .prop_map(|(players, planets)| GameState { players, planets })
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip_primitives(f in coordinate(), i in any::<i32>(), u in any::<usize>(), b in any::<bool>()) {
+            round_trip(&f);
+            round_trip(&i);
+            round_trip(&u);
+            round_trip(&b);
+        }
+
+        #[test]
+        fn test_round_trip_containers(values in prop::collection::vec(any::<Option<i32>>(), 0..8)) {
+            round_trip(&values);
+        }
+
+        #[test]
+        fn test_round_trip_entities(position in position(), status in docking_status(), ship in ship(), planet in planet()) {
+            round_trip(&position);
+            round_trip(&status);
+            round_trip(&ship);
+            round_trip(&planet);
+        }
+
+        #[test]
+        fn test_round_trip_game_state(state in game_state()) {
+            round_trip(&state);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        // the line test_parse_errors starts from, which the engine could have sent word for word
+        let line = "1 0 1 4 1.5 2.5 255 0 0 0 0 0 0 0";
+        let state = GameState::parse(&mut Tokens::new(line)).unwrap();
+        assert_eq!(line, state.to_wire());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let state = GameState::parse(&mut Tokens::new("1 0 1 4 1.5 2.5 255 0 0 0 0 0 0 0")).unwrap();
+        assert_eq!(1, state.players[0].ships.len());
+
+        let error = GameState::parse(&mut Tokens::new("1 0 1 4 1.5 2.5 255 0 0 x 0 0 0 0")).unwrap_err();
+        assert_eq!(
+            ParseError {
+                index: 9,
+                expected: "DockingStatus",
+                found: Some("x".to_string()),
+            },
+            error
+        );
+        let error = GameState::parse(&mut Tokens::new("1 0 1 4 1.5 2.5 255 0 0 7 0 0 0 0")).unwrap_err();
+        assert_eq!(Some("7".to_string()), error.found);
+        let error = GameState::parse(&mut Tokens::new("1 0 1 4 1.5")).unwrap_err();
+        assert_eq!((5, None), (error.index, error.found));
+        // a count far beyond what the line holds runs out of tokens rather than memory
+        let error = GameState::parse(&mut Tokens::new("1 0 100000000000000 4")).unwrap_err();
+        assert_eq!((4, None), (error.index, error.found));
     }
 }
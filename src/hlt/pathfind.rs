@@ -1,18 +1,17 @@
+use hlt::collision::intersect_segment_circle;
+use hlt::constants::{FUDGE, SHIP_RADIUS};
+use hlt::angle::Angle;
+use hlt::entity::{Entity, Obstacle, Position};
+use hlt::game_map::GameMap;
+use hlt::vector::Vec2;
 
-use hlt::entity::{Entity, Position};
-macro_rules! in_360 (
-    ($angle:expr) => ($angle % 360.0)
-    );
-
-fn angle_around(start: Position, destination: Position, obstacle_pos: Position, obstacle_size: f64) -> (f64, f64) {
-    // let mut logger = Logger::new(0);
+// the two angles a ship at start can fly at to just clear the obstacle, the one closer to the way to destination
+// first
+fn angle_around(start: Position, destination: Position, obstacle_pos: Position, obstacle_size: f64) -> (Angle, Angle) {
     // s = start position
     // o = obstacle position
-    // d = destination position
-    // tan = position where trajectory is tangent to obstacle entering arc
-    // tan2 = position where trajectory is tangent to obstacle leaving arc
+    // tan = position where trajectory is tangent to obstacle
     let d_s_o = start.distance_to(&obstacle_pos);
-    // let s_o_d_angle = three_point_angle(start, obstacle_pos, destination);
 
     // deal with case where ship is inside navigation radius
     let s_o_tan_angle = if obstacle_size > d_s_o {
@@ -20,50 +19,28 @@ fn angle_around(start: Position, destination: Position, obstacle_pos: Position,
     } else {
         (obstacle_size / d_s_o).acos()
     };
-    let s_o_tan_angle = s_o_tan_angle.to_degrees();
-    let turn_angle = 90.0 - s_o_tan_angle;
-
-    let x_delt = destination.0 - start.0;
-    let y_delt = destination.1 - start.1;
-    let angle_to_dest = in_360!(y_delt.atan2(x_delt).to_degrees());
-
-    let x_delt = obstacle_pos.0 - start.0;
-    let y_delt = obstacle_pos.1 - start.1;
-    let angle_to_obstacle = in_360!(y_delt.atan2(x_delt).to_degrees());
-
-    if angle_between(angle_to_dest, in_360!(angle_to_obstacle + turn_angle))
-        < angle_between(angle_to_dest, in_360!(angle_to_obstacle - turn_angle))
-    {
-        (
-            in_360!(angle_to_obstacle + turn_angle),
-            in_360!(angle_to_obstacle - turn_angle),
-        )
+    let turn_angle = 90.0 - s_o_tan_angle.to_degrees();
+
+    let angle_to_dest = Angle::between(&start, &destination);
+    let angle_to_obstacle = Angle::between(&start, &obstacle_pos);
+    let (left, right) = (angle_to_obstacle.rotate(turn_angle), angle_to_obstacle.rotate(-turn_angle));
+
+    if angle_to_dest.difference(left).abs() < angle_to_dest.difference(right).abs() {
+        (left, right)
     } else {
-        (
-            in_360!(angle_to_obstacle - turn_angle),
-            in_360!(angle_to_obstacle + turn_angle),
-        )
+        (right, left)
     }
 }
 
 #[allow(dead_code)]
-pub fn long_angle_around(start: Position, destination: Position, obstacle_pos: Position, obstacle_size: f64) -> f64 {
+pub fn long_angle_around(start: Position, destination: Position, obstacle_pos: Position, obstacle_size: f64) -> Angle {
     angle_around(start, destination, obstacle_pos, obstacle_size).1
 }
 
-pub fn short_angle_around(start: Position, destination: Position, obstacle_pos: Position, obstacle_size: f64) -> f64 {
+pub fn short_angle_around(start: Position, destination: Position, obstacle_pos: Position, obstacle_size: f64) -> Angle {
     angle_around(start, destination, obstacle_pos, obstacle_size).0
 }
 
-fn angle_between(a1: f64, a2: f64) -> f64 {
-    let da = (a1 - a2).abs();
-    if da > 180.0 {
-        180.0 - da
-    } else {
-        da
-    }
-}
-
 #[allow(dead_code)]
 pub fn three_point_angle(p1: Position, p2: Position, p3: Position) -> f64 {
     let d12 = p1.distance_to(&p2);
@@ -72,10 +49,354 @@ pub fn three_point_angle(p1: Position, p2: Position, p3: Position) -> f64 {
     ((d12.powi(2) + d13.powi(2) - d23.powi(2)) / (2f64 * d12 * d13)).acos()
 }
 
-/* pathfinding idea: take long and short angle around O as two choices,
- * where O is the first object
- * between the ship and its destination. Extend path along that angle until
- * O is no longer the
- * first object between ship and dest. Repeat for additional obstacles.
- * Points where the obstacle
- * between the ship and destination are the graph nodes */
+// times the obstacles found in the way of the best path so far are added in before giving up
+const MAX_ROUNDS: usize = 8;
+// most degrees of an arc around an obstacle between the points it's checked and flown by
+const ARC_STEP: f64 = 10.0;
+
+// Where a ship at start should head first on the shortest way to destination around planets and ships which are
+// not undocked, or None if there's no way through. Each obstacle grown by the ship's radius is a circle, and the
+// shortest way around circles is made of lines tangent to them and arcs along them, so A* runs over the graph of
+// the points where the lines from start and destination and the lines tangent to two circles at once touch the
+// circles, joined by those lines and by the arcs in between. Only the obstacles in the way of the straight line
+// are considered at first, then those in the way of the best path found with them and so on, so the graph stays
+// small. Obstacles the ship or its destination are inside of are left out, there's no going around those
+pub fn first_waypoint(start: &Position, destination: &Position, game_map: &GameMap) -> Option<Position> {
+    let mut obstacles: Vec<Obstacle> = vec![];
+    for _ in 0..MAX_ROUNDS {
+        let path = shortest_path(start, destination, &obstacles, game_map.width(), game_map.height())?;
+        let in_the_way: Vec<Obstacle> = path.windows(2)
+            .flat_map(|leg| in_the_way(&leg[0], &leg[1], start, destination, game_map))
+            .filter(|o| !obstacles.iter().any(|known| known.position == o.position))
+            .collect();
+        if in_the_way.is_empty() {
+            return Some(path[1]);
+        }
+        for obstacle in in_the_way {
+            if !obstacles.iter().any(|known| known.position == obstacle.position) {
+                obstacles.push(obstacle);
+            }
+        }
+    }
+    None
+}
+
+// whether a ship at start can head straight for waypoint on the way to destination, the same as if first_waypoint
+// had found it
+pub fn clear_to_waypoint(start: &Position, waypoint: &Position, destination: &Position, game_map: &GameMap) -> bool {
+    in_the_way(start, waypoint, start, destination, game_map).is_empty()
+}
+
+// the obstacles on the leg from one point to another of the way from start to destination, bar those start or
+// destination are inside of
+fn in_the_way(
+    from: &Position,
+    to: &Position,
+    start: &Position,
+    destination: &Position,
+    game_map: &GameMap,
+) -> Vec<Obstacle> {
+    let clearance = SHIP_RADIUS + FUDGE;
+    let enclosing = |o: &Obstacle, p: &Position| o.position.distance_to(p) < o.radius + clearance;
+    game_map
+        .stationary_obstacles_between(from, to, FUDGE)
+        .into_iter()
+        .filter(|o| !enclosing(o, start) && !enclosing(o, destination))
+        .collect()
+}
+
+// the points where the two lines from point which touch the circle around center do, none if point is inside it
+fn tangent_points(point: &Position, center: &Position, radius: f64) -> Vec<Position> {
+    let distance = point.distance_to(center);
+    if distance <= radius {
+        return vec![];
+    }
+    let towards_point = Angle::between(center, point);
+    let turn = (radius / distance).acos().to_degrees();
+    vec![
+        *center + Position::from_polar(radius, towards_point.rotate(turn)),
+        *center + Position::from_polar(radius, towards_point.rotate(-turn)),
+    ]
+}
+
+// the points where the lines which touch both circles touch the first and the second one, the two lines with both
+// circles on the same side unless one is inside the other and the two crossing between them unless they overlap
+fn bitangent_points(c1: &Position, r1: f64, c2: &Position, r2: f64) -> Vec<(Position, Position)> {
+    let distance = c1.distance_to(c2);
+    let towards = Angle::between(c1, c2);
+    let mut points = vec![];
+    if distance > (r1 - r2).abs() {
+        let turn = ((r1 - r2) / distance).acos().to_degrees();
+        for &side in [turn, -turn].iter() {
+            let normal = towards.rotate(side);
+            points.push((*c1 + Position::from_polar(r1, normal), *c2 + Position::from_polar(r2, normal)));
+        }
+    }
+    if distance > r1 + r2 {
+        let turn = ((r1 + r2) / distance).acos().to_degrees();
+        for &side in [turn, -turn].iter() {
+            let normal = towards.rotate(side);
+            points.push((
+                *c1 + Position::from_polar(r1, normal),
+                *c2 + Position::from_polar(r2, normal.rotate(180.0)),
+            ));
+        }
+    }
+    points
+}
+
+// a way from one node of the graph to another, by the points of an arc in between if it goes along a circle
+struct Edge {
+    to: usize,
+    cost: f64,
+    via: Vec<Position>,
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Position>,
+    circles: Vec<Option<usize>>,
+    edges: Vec<Vec<Edge>>,
+}
+
+impl Graph {
+    fn add(&mut self, position: Position, circle: Option<usize>) -> usize {
+        self.nodes.push(position);
+        self.circles.push(circle);
+        self.edges.push(vec![]);
+        self.nodes.len() - 1
+    }
+
+    fn connect(&mut self, from: usize, to: usize, cost: f64, via: Vec<Position>) {
+        let back = via.iter().rev().cloned().collect();
+        self.edges[from].push(Edge { to, cost, via });
+        self.edges[to].push(Edge { to: from, cost, via: back });
+    }
+}
+
+// the shortest path from start to destination, both included, that stays clear of the obstacles and inside the
+// map, with the arcs around obstacles broken up into legs no longer than ARC_STEP degrees
+fn shortest_path(
+    start: &Position,
+    destination: &Position,
+    obstacles: &[Obstacle],
+    width: f64,
+    height: f64,
+) -> Option<Vec<Position>> {
+    let clearance = SHIP_RADIUS + FUDGE;
+    let circles: Vec<(Position, f64)> = obstacles.iter().map(|o| (o.position, o.radius + clearance)).collect();
+    let clear = |p: &Position| {
+        p.0 >= clearance && p.1 >= clearance && p.0 <= width - clearance && p.1 <= height - clearance
+            && !circles.iter().any(|&(center, radius)| center.distance_to(p) < radius)
+    };
+    let visible = |a: &Position, b: &Position| {
+        !circles.iter().any(|&(center, radius)| intersect_segment_circle(a, b, &center, radius))
+    };
+    // paths touch the circles a bit further out, so the lines tangent to them count as clear of them
+    let touching = |radius: f64| radius + FUDGE;
+
+    let mut graph = Graph::default();
+    graph.add(*start, None);
+    graph.add(*destination, None);
+    if visible(start, destination) {
+        graph.connect(0, 1, start.distance_to(destination), vec![]);
+    }
+    for (i, &(center, radius)) in circles.iter().enumerate() {
+        for &(end, node) in [(*start, 0), (*destination, 1)].iter() {
+            for p in tangent_points(&end, &center, touching(radius)) {
+                if clear(&p) && visible(&end, &p) {
+                    let n = graph.add(p, Some(i));
+                    graph.connect(node, n, end.distance_to(&p), vec![]);
+                }
+            }
+        }
+        for (j, &(other_center, other_radius)) in circles.iter().enumerate().skip(i + 1) {
+            for (p, q) in bitangent_points(&center, touching(radius), &other_center, touching(other_radius)) {
+                if clear(&p) && clear(&q) && visible(&p, &q) {
+                    let (n1, n2) = (graph.add(p, Some(i)), graph.add(q, Some(j)));
+                    graph.connect(n1, n2, p.distance_to(&q), vec![]);
+                }
+            }
+        }
+    }
+    // each node on a circle to the next one around it, if the arc between them is clear
+    for (i, &(center, radius)) in circles.iter().enumerate() {
+        let radius = touching(radius);
+        let mut around: Vec<(f64, usize)> = (0..graph.nodes.len())
+            .filter(|&n| graph.circles[n] == Some(i))
+            .map(|n| (Angle::between(&center, &graph.nodes[n]).to_degrees(), n))
+            .collect();
+        if around.len() < 2 {
+            continue;
+        }
+        around.sort_by(|a1, a2| a1.0.partial_cmp(&a2.0).unwrap());
+        for k in 0..around.len() {
+            let ((from_angle, from), (to_angle, to)) = (around[k], around[(k + 1) % around.len()]);
+            let sweep = (to_angle - from_angle).rem_euclid(360.0);
+            if sweep == 0.0 {
+                continue;
+            }
+            let steps = (sweep / ARC_STEP).ceil() as usize;
+            let via: Vec<Position> = (1..steps)
+                .map(|s| Angle::degrees(from_angle + sweep * s as f64 / steps as f64))
+                .map(|a| center + Position::from_polar(radius, a))
+                .collect();
+            if via.iter().all(&clear) {
+                graph.connect(from, to, radius * sweep.to_radians(), via);
+            }
+        }
+    }
+
+    // A*, with the straight line distance to the destination as the heuristic
+    let nodes = &graph.nodes;
+    let mut cost = vec![f64::INFINITY; nodes.len()];
+    let mut previous: Vec<Option<(usize, usize)>> = vec![None; nodes.len()];
+    let mut open = vec![false; nodes.len()];
+    let mut closed = vec![false; nodes.len()];
+    cost[0] = 0.0;
+    open[0] = true;
+    while let Some(current) = (0..nodes.len())
+        .filter(|&n| open[n])
+        .min_by(|&n1, &n2| {
+            (cost[n1] + nodes[n1].distance_to(destination))
+                .partial_cmp(&(cost[n2] + nodes[n2].distance_to(destination)))
+                .unwrap()
+        }) {
+        if current == 1 {
+            let mut path = vec![nodes[1]];
+            let mut node = 1;
+            while let Some((p, e)) = previous[node] {
+                path.extend(graph.edges[p][e].via.iter().rev());
+                path.push(nodes[p]);
+                node = p;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        open[current] = false;
+        closed[current] = true;
+        for (e, edge) in graph.edges[current].iter().enumerate() {
+            if closed[edge.to] {
+                continue;
+            }
+            let through_current = cost[current] + edge.cost;
+            if through_current < cost[edge.to] {
+                cost[edge.to] = through_current;
+                previous[edge.to] = Some((current, e));
+                open[edge.to] = true;
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use hlt::angle::Angle;
+    use hlt::constants::{FUDGE, SHIP_RADIUS};
+    use hlt::entity::{Entity, GameState, Planet, Position};
+    use hlt::game::Game;
+    use hlt::game_map::GameMap;
+    use hlt::pathfind::{clear_to_waypoint, first_waypoint, long_angle_around, short_angle_around};
+    use hlt::vector::Vec2;
+
+    const EPSILON: f64 = 1e-6;
+
+    fn degrees() -> impl Strategy<Value = f64> {
+        -1080.0..1080.0
+    }
+
+    // how far apart two angles are either way around
+    fn apart(a1: Angle, a2: Angle) -> f64 {
+        a1.difference(a2).abs()
+    }
+
+    proptest! {
+        #[test]
+        fn test_angle_around(obstacle in degrees(), destination in degrees(), radius in 1.0..10.0f64) {
+            // an obstacle between the ship and anything, in every direction
+            let start = Position(120.0, 80.0);
+            let at = |angle: Angle, distance: f64| start + Position::from_polar(distance, angle);
+            let (obstacle, destination) = (Angle::degrees(obstacle), Angle::degrees(destination));
+            let obstacle_pos = at(obstacle, 20.0);
+            let short = short_angle_around(start, at(destination, 50.0), obstacle_pos, radius);
+            let long = long_angle_around(start, at(destination, 50.0), obstacle_pos, radius);
+            // both graze the obstacle, one on either side of it
+            for &angle in [short, long].iter() {
+                let passing_distance = start.distance_to(&obstacle_pos) * apart(angle, obstacle).to_radians().sin();
+                prop_assert!(apart(angle, obstacle) < 90.0);
+                prop_assert!((passing_distance - radius).abs() < EPSILON);
+            }
+            prop_assert!((obstacle.difference(short) + obstacle.difference(long)).abs() < EPSILON);
+            prop_assert!(apart(destination, short) <= apart(destination, long) + EPSILON);
+        }
+    }
+
+    fn game_map(game: &Game, planets: Vec<Planet>) -> GameMap<'_> {
+        GameMap::new(game, GameState { players: vec![], planets })
+    }
+
+    #[test]
+    fn test_straight_line_when_clear() {
+        let game = Game::offline(0, 240, 160);
+        let game_map = game_map(&game, vec![Planet::new(0, Position(100.0, 100.0), 1000, 5.0, 3, 1000)]);
+        let (start, destination) = (Position(20.0, 20.0), Position(60.0, 20.0));
+        assert_eq!(Some(destination), first_waypoint(&start, &destination, &game_map));
+        assert_eq!(Some(destination), game_map.waypoint(&start, &destination));
+    }
+
+    #[test]
+    fn test_tangent_around_a_planet() {
+        let game = Game::offline(0, 240, 160);
+        let planet = Planet::new(0, Position(100.0, 80.0), 1000, 10.0, 3, 1000);
+        let center = planet.get_position();
+        let game_map = game_map(&game, vec![planet]);
+        let (start, destination) = (Position(60.0, 81.0), Position(140.0, 81.0));
+        let waypoint = first_waypoint(&start, &destination, &game_map).unwrap();
+        // the line to the waypoint just touches the planet there, on the side the way is shorter around
+        let radius = 10.0 + SHIP_RADIUS + 2.0 * FUDGE;
+        assert!((waypoint.distance_to(&center) - radius).abs() < EPSILON);
+        assert!((waypoint - start).dot(waypoint - center).abs() < EPSILON);
+        assert!(waypoint.1 > center.1);
+    }
+
+    #[test]
+    fn test_waypoint_shared_by_close_starts() {
+        let game = Game::offline(0, 240, 160);
+        let game_map = game_map(&game, vec![Planet::new(0, Position(100.0, 80.0), 1000, 10.0, 3, 1000)]);
+        let destination = Position(140.0, 81.0);
+        let (first, second) = (Position(60.2, 80.2), Position(60.9, 80.9));
+        let waypoint = game_map.waypoint(&first, &destination).unwrap();
+        assert_eq!(Some(waypoint), game_map.waypoint(&second, &destination));
+        assert!(clear_to_waypoint(&second, &waypoint, &destination, &game_map));
+        // but not ones somewhere else
+        let elsewhere = Position(60.9, 79.1);
+        assert_eq!(first_waypoint(&elsewhere, &destination, &game_map), game_map.waypoint(&elsewhere, &destination));
+    }
+
+    #[test]
+    fn test_around_planets_in_a_row() {
+        // a wall of planets straight across the way, with a gap at its far end
+        let game = Game::offline(0, 240, 160);
+        let planets: Vec<Planet> = (0..5)
+            .map(|id| Planet::new(id, Position(100.0, 30.0 + 20.0 * id as f64), 1000, 10.0, 3, 1000))
+            .collect();
+        let game_map = game_map(&game, planets);
+        let (start, destination) = (Position(80.0, 60.0), Position(120.0, 60.0));
+        let waypoint = first_waypoint(&start, &destination, &game_map).unwrap();
+        assert!(game_map.closest_stationary_obstacle(&start, &waypoint, 0.0).is_none());
+        // the wall runs furthest downwards, so the way around it is upwards
+        assert!(waypoint.1 < start.1);
+        assert!(waypoint.1 < 30.0);
+
+        // a wall from one edge of the map to the other can't be got around
+        let planets = vec![
+            Planet::new(0, Position(120.0, 40.0), 1000, 40.0, 3, 1000),
+            Planet::new(1, Position(120.0, 120.0), 1000, 40.0, 3, 1000),
+        ];
+        let game_map = self::game_map(&game, planets);
+        assert_eq!(None, first_waypoint(&Position(20.0, 80.0), &Position(220.0, 80.0), &game_map));
+    }
+}
@@ -1,7 +1,7 @@
 use hlt::entity::Ship;
-use hlt::parse::Decodable;
+use hlt::parse::{Decodable, Encodable, ParseError, Tokens};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Player {
     pub id: i32,
     pub ships: Vec<Ship>,
@@ -15,18 +15,26 @@ impl Player {
 }
 
 impl Decodable for Player {
-    fn parse<'a, I>(tokens: &mut I) -> Player
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let id = i32::parse(tokens);
-        let ships = Vec::parse(tokens);
+    fn parse(tokens: &mut Tokens) -> Result<Player, ParseError> {
+        let id = i32::parse(tokens)?;
+        let mut ships: Vec<Ship> = Vec::parse(tokens)?;
+        // the owner isn't sent with each ship
+        for ship in ships.iter_mut() {
+            ship.owner_id = id;
+        }
         let strength = 0.0;
 
-        return Player {
+        Ok(Player {
             id,
             ships,
             strength,
-        };
+        })
+    }
+}
+
+impl Encodable for Player {
+    fn encode(&self, tokens: &mut Vec<String>) {
+        self.id.encode(tokens);
+        self.ships.encode(tokens);
     }
 }
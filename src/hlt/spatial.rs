@@ -0,0 +1,151 @@
+use hlt::entity::{Entity, GameState, Position};
+
+// a bit over weapon range plus a turn of movement, so most queries the bot makes touch 9 cells or fewer
+const CELL_SIZE: f64 = 16.0;
+
+// Uniform grid over the map, built once a turn, bucketing ships and planets by the cell their center falls in.
+// Ships are kept as (index into GameState::players, index into that player's ships) and planets as their index into
+// GameState::planets, in that order within every cell, so queries hand entities back in the order a scan of the
+// whole state would meet them. Anything off the map goes in the nearest edge cell
+pub struct SpatialIndex {
+    columns: usize,
+    rows: usize,
+    ships: Vec<Vec<(usize, usize)>>,
+    planets: Vec<Vec<usize>>,
+    // planets are bucketed by their center but can reach well into neighbouring cells, so planet queries are
+    // widened by the largest radius
+    max_planet_radius: f64,
+}
+
+impl SpatialIndex {
+    pub fn new(state: &GameState, width: f64, height: f64) -> SpatialIndex {
+        let columns = ((width / CELL_SIZE).ceil() as usize).max(1);
+        let rows = ((height / CELL_SIZE).ceil() as usize).max(1);
+        let mut index = SpatialIndex {
+            columns,
+            rows,
+            ships: vec![vec![]; columns * rows],
+            planets: vec![vec![]; columns * rows],
+            max_planet_radius: 0.0,
+        };
+        for (p, player) in state.players.iter().enumerate() {
+            for (s, ship) in player.ships.iter().enumerate() {
+                let cell = index.cell(&ship.get_position());
+                index.ships[cell].push((p, s));
+            }
+        }
+        for (p, planet) in state.planets.iter().enumerate() {
+            let cell = index.cell(&planet.get_position());
+            index.planets[cell].push(p);
+            index.max_planet_radius = index.max_planet_radius.max(planet.get_radius());
+        }
+        index
+    }
+
+    fn column(&self, x: f64) -> usize {
+        ((x / CELL_SIZE).floor().max(0.0) as usize).min(self.columns - 1)
+    }
+
+    fn row(&self, y: f64) -> usize {
+        ((y / CELL_SIZE).floor().max(0.0) as usize).min(self.rows - 1)
+    }
+
+    fn cell(&self, position: &Position) -> usize {
+        self.row(position.1) * self.columns + self.column(position.0)
+    }
+
+    // every cell overlapping the square around center
+    fn cells_near(&self, center: &Position, radius: f64) -> Vec<usize> {
+        let (left, right) = (self.column(center.0 - radius), self.column(center.0 + radius));
+        let (top, bottom) = (self.row(center.1 - radius), self.row(center.1 + radius));
+        (top..=bottom)
+            .flat_map(|row| (left..=right).map(move |column| (row, column)))
+            .map(|(row, column)| row * self.columns + column)
+            .collect()
+    }
+
+    // every ship whose center might be within radius of center, and then some, in state order
+    pub fn ships_near(&self, center: &Position, radius: f64) -> Vec<(usize, usize)> {
+        let mut ships: Vec<(usize, usize)> = self.cells_near(center, radius)
+            .into_iter()
+            .flat_map(|cell| self.ships[cell].iter().cloned())
+            .collect();
+        ships.sort();
+        ships
+    }
+
+    // every planet whose surface might be within radius of center, and then some, in state order
+    pub fn planets_near(&self, center: &Position, radius: f64) -> Vec<usize> {
+        let mut planets: Vec<usize> = self.cells_near(center, radius + self.max_planet_radius)
+            .into_iter()
+            .flat_map(|cell| self.planets[cell].iter().cloned())
+            .collect();
+        planets.sort();
+        planets
+    }
+
+    // how far a search has to reach to be sure of covering the whole map from anywhere on it
+    pub fn extent(&self) -> f64 {
+        CELL_SIZE * (self.columns.max(self.rows) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::entity::{Entity, GameState, Planet, Position, Ship};
+    use hlt::player::Player;
+    use hlt::spatial::SpatialIndex;
+
+    #[test]
+    fn test_spatial_index() {
+        let positions = [(1.0, 1.0), (20.0, 5.0), (100.0, 100.0), (-5.0, 300.0), (17.0, 17.0)];
+        let ships: Vec<Ship> = positions
+            .iter()
+            .enumerate()
+            .map(|(id, &(x, y))| Ship::new(id as i32, (id % 2) as i32, Position(x, y)))
+            .collect();
+        let state = GameState {
+            players: vec![
+                Player {
+                    id: 0,
+                    ships: ships.iter().filter(|s| s.owner_id == 0).cloned().collect(),
+                    strength: 0.0,
+                },
+                Player {
+                    id: 1,
+                    ships: ships.iter().filter(|s| s.owner_id == 1).cloned().collect(),
+                    strength: 0.0,
+                },
+            ],
+            planets: vec![
+                Planet::new(0, Position(60.0, 60.0), 1000, 10.0, 3, 1000),
+                Planet::new(1, Position(200.0, 10.0), 1000, 4.0, 3, 1000),
+            ],
+        };
+        let index = SpatialIndex::new(&state, 240.0, 160.0);
+
+        // every ship within the radius is found, whatever else comes with it
+        for &(x, y, radius) in [(0.0, 0.0, 25.0), (100.0, 90.0, 10.0), (0.0, 160.0, 1.0), (50.0, 50.0, 0.0)].iter() {
+            let center = Position(x, y);
+            let near = index.ships_near(&center, radius);
+            let mut sorted = near.clone();
+            sorted.sort();
+            assert_eq!(sorted, near);
+            for (p, player) in state.players.iter().enumerate() {
+                for (s, ship) in player.ships.iter().enumerate() {
+                    if ship.get_position().distance_to(&center) <= radius {
+                        assert!(near.contains(&(p, s)), "{:?} missed at {:?}", ship.get_position(), center);
+                    }
+                }
+            }
+        }
+        assert!(index.ships_near(&Position(200.0, 100.0), 5.0).is_empty());
+        // the off map ship sits in the bottom left cell
+        assert_eq!(vec![(1, 1)], index.ships_near(&Position(0.0, 159.0), 1.0));
+
+        // planets are found by their surface, not just their center
+        assert_eq!(vec![0], index.planets_near(&Position(45.0, 60.0), 6.0));
+        assert_eq!(vec![0, 1], index.planets_near(&Position(120.0, 40.0), 100.0));
+        assert!(index.extent() >= 240.0);
+    }
+}
@@ -0,0 +1,58 @@
+use std::io::{self, stdin, stdout, BufRead, StdinLock, Stdout, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+// where Game reads map lines from and writes command lines to
+pub trait Transport {
+    // the next line including its newline, or an empty string once the input has ended
+    fn read_line(&mut self) -> io::Result<String>;
+    fn write_line(&mut self, line: &str) -> io::Result<()>;
+}
+
+// any reader and writer, flushed after every line so the engine sees each turn's commands straight away
+pub struct Streams<R: BufRead, W: Write> {
+    pub reader: R,
+    pub writer: W,
+}
+
+impl<R: BufRead, W: Write> Streams<R, W> {
+    pub fn new(reader: R, writer: W) -> Streams<R, W> {
+        Streams { reader, writer }
+    }
+}
+
+// the default, talking to the engine over stdin and stdout
+pub fn stdio() -> Streams<StdinLock<'static>, Stdout> {
+    Streams::new(stdin().lock(), stdout())
+}
+
+impl<R: BufRead, W: Write> Transport for Streams<R, W> {
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut buffer = String::new();
+        self.reader.read_line(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()
+    }
+}
+
+// a pair of channels carrying one line per message, for running a bot on a thread of the engine's process
+pub struct Channels {
+    pub input: Receiver<String>,
+    pub output: Sender<String>,
+}
+
+impl Transport for Channels {
+    fn read_line(&mut self) -> io::Result<String> {
+        // a closed channel is the end of the input, like a closed pipe
+        Ok(self.input.recv().map(|line| line + "\n").unwrap_or_default())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.output
+            .send(line.to_string())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "engine stopped listening"))
+    }
+}
@@ -0,0 +1,154 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use hlt::entity::Position;
+use hlt::angle::Angle;
+
+// Plane vector math, for positions as well as the offsets and velocities between them. Everything but the
+// coordinates comes for free. Positions also add, subtract, negate and scale with the usual operators
+pub trait Vec2: Copy {
+    fn new(x: f64, y: f64) -> Self;
+    fn x(&self) -> f64;
+    fn y(&self) -> f64;
+
+    // length towards angle, e.g. the velocity a thrust gives
+    fn from_polar(length: f64, angle: Angle) -> Self {
+        Self::new(length * angle.to_radians().cos(), length * angle.to_radians().sin())
+    }
+
+    fn scale(self, factor: f64) -> Self {
+        Self::new(factor * self.x(), factor * self.y())
+    }
+
+    fn dot(self, other: Self) -> f64 {
+        self.x() * other.x() + self.y() * other.y()
+    }
+
+    // the z of the 3d cross product, positive when other is a turn towards the y axis from this one
+    fn cross(self, other: Self) -> f64 {
+        self.x() * other.y() - self.y() * other.x()
+    }
+
+    fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    // the same direction at length 1, or the zero vector for the zero vector
+    fn normalize(self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self.scale(1.0 / length)
+        }
+    }
+
+    // turned by angle towards the y axis
+    fn rotate(self, angle: Angle) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self::new(cos * self.x() - sin * self.y(), sin * self.x() + cos * self.y())
+    }
+
+    fn angle(self) -> Angle {
+        Angle::radians(self.y().atan2(self.x()))
+    }
+}
+
+impl Vec2 for Position {
+    fn new(x: f64, y: f64) -> Position {
+        Position(x, y)
+    }
+
+    fn x(&self) -> f64 {
+        self.0
+    }
+
+    fn y(&self) -> f64 {
+        self.1
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, other: Position) -> Position {
+        Position(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    fn sub(self, other: Position) -> Position {
+        Position(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+impl Mul<f64> for Position {
+    type Output = Position;
+
+    fn mul(self, factor: f64) -> Position {
+        self.scale(factor)
+    }
+}
+
+impl Neg for Position {
+    type Output = Position;
+
+    fn neg(self) -> Position {
+        self.scale(-1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use hlt::entity::Position;
+    use hlt::angle::Angle;
+    use hlt::vector::Vec2;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn vector() -> impl Strategy<Value = Position> {
+        (-300.0..300.0, -300.0..300.0).prop_map(|(x, y)| Position(x, y))
+    }
+
+    #[test]
+    fn test_ops() {
+        let (a, b) = (Position(3.0, 4.0), Position(-1.0, 2.0));
+        assert_eq!(Position(2.0, 6.0), a + b);
+        assert_eq!(Position(4.0, 2.0), a - b);
+        assert_eq!(Position(6.0, 8.0), a * 2.0);
+        assert_eq!(Position(-3.0, -4.0), -a);
+        assert_eq!(5.0, a.dot(b));
+        assert_eq!(10.0, a.cross(b));
+        assert_eq!(5.0, a.length());
+        assert_eq!(Position(0.0, -1.0), Position(0.0, -4.0).normalize());
+        assert!((a.normalize() - Position(0.6, 0.8)).length() < EPSILON);
+        assert_eq!(Position(0.0, 0.0), Position(0.0, 0.0).normalize());
+    }
+
+    proptest! {
+        #[test]
+        fn test_polar_round_trip(length in 0.01..300.0f64, degrees in 0.0..360.0f64) {
+            let v = Position::from_polar(length, Angle::degrees(degrees));
+            prop_assert!((v.length() - length).abs() < EPSILON * length.max(1.0));
+            prop_assert!(v.angle().difference(Angle::degrees(degrees)).abs() < EPSILON);
+        }
+
+        #[test]
+        fn test_rotate(v in vector(), degrees in -720.0..720.0f64) {
+            let rotated = v.rotate(Angle::degrees(degrees));
+            prop_assert!((rotated.length() - v.length()).abs() < 1e-6);
+            prop_assert!((rotated.rotate(Angle::degrees(-degrees)) - v).length() < 1e-6);
+            // a quarter turn towards the y axis lines up with the cross product
+            let quarter = v.rotate(Angle::degrees(90.0));
+            prop_assert!(quarter.dot(v).abs() < 1e-6 * v.length_squared().max(1.0));
+            prop_assert!(v.cross(quarter) >= 0.0);
+        }
+    }
+}
@@ -0,0 +1,12 @@
+#[cfg(test)]
+extern crate proptest;
+#[macro_use]
+extern crate serde_json;
+extern crate time;
+#[cfg(feature = "zstd")]
+extern crate zstd;
+
+#[macro_use]
+pub mod hlt;
+pub mod engine;
+pub mod strategy;
@@ -0,0 +1,18 @@
+use time::PreciseTime;
+
+use hlt::command::Command;
+use hlt::game_map::GameMap;
+use strategy::{attack, Strategy};
+
+// never docks, every ship chases down the enemy ship nearest to it
+pub struct Attacker;
+
+impl Strategy for Attacker {
+    fn on_turn(&mut self, game_map: &GameMap, _turn: usize, _start: PreciseTime) -> Vec<Command> {
+        game_map
+            .my_undocked_ships()
+            .into_iter()
+            .filter_map(|ship| attack(ship, game_map))
+            .collect()
+    }
+}
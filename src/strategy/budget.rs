@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use time::PreciseTime;
+
+use hlt::command::Command;
+use hlt::entity::Ship;
+use hlt::game_map::GameMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    // guessing where enemy ships are headed
+    Prediction,
+    // working out every ship's candidate moves
+    MoveGeneration,
+    // picking which ship moves next and where
+    Assignment,
+    // turning moves into thrusts around obstacles
+    Navigation,
+}
+
+pub const PHASES: [Phase; 4] = [Phase::Prediction, Phase::MoveGeneration, Phase::Assignment, Phase::Navigation];
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Phase::Prediction => "prediction",
+            Phase::MoveGeneration => "move generation",
+            Phase::Assignment => "assignment",
+            Phase::Navigation => "navigation",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+fn milliseconds(from: PreciseTime, to: PreciseTime) -> f64 {
+    from.to(to).num_microseconds().unwrap_or(i64::MAX) as f64 / 1000.0
+}
+
+// Time spent on a turn, split by phase. Past the soft deadline no new work should be started, past the hard one
+// nothing more should be worked out at all and any ship without a command gets a fallback move
+pub struct TimeBudget {
+    start: PreciseTime,
+    soft_ms: f64,
+    hard_ms: f64,
+    current: Option<(Phase, PreciseTime)>,
+    spent_ms: [f64; 4],
+}
+
+impl TimeBudget {
+    // a budget for the turn that started at start, i.e. when its map line was read
+    pub fn new(start: PreciseTime, soft_ms: f64, hard_ms: f64) -> TimeBudget {
+        TimeBudget {
+            start,
+            soft_ms,
+            hard_ms,
+            current: None,
+            spent_ms: [0.0; 4],
+        }
+    }
+
+    pub fn elapsed_ms(&self) -> f64 {
+        milliseconds(self.start, PreciseTime::now())
+    }
+
+    pub fn soft_expired(&self) -> bool {
+        self.elapsed_ms() > self.soft_ms
+    }
+
+    pub fn hard_expired(&self) -> bool {
+        self.elapsed_ms() > self.hard_ms
+    }
+
+    // ends the current phase, if any, and starts timing the given one
+    pub fn enter(&mut self, phase: Phase) {
+        let now = PreciseTime::now();
+        self.end_at(now);
+        self.current = Some((phase, now));
+    }
+
+    pub fn end(&mut self) {
+        self.end_at(PreciseTime::now());
+    }
+
+    fn end_at(&mut self, now: PreciseTime) {
+        if let Some((phase, started)) = self.current.take() {
+            self.spent_ms[PHASES.iter().position(|p| *p == phase).unwrap()] += milliseconds(started, now);
+        }
+    }
+
+    pub fn spent_ms(&self, phase: Phase) -> f64 {
+        self.spent_ms[PHASES.iter().position(|p| *p == phase).unwrap()]
+    }
+
+    // one line for the log, e.g. "prediction 0.4ms, move generation 31.0ms, ..., total 52.3ms"
+    pub fn report(&self) -> String {
+        let phases: Vec<String> = PHASES
+            .iter()
+            .map(|phase| format!("{} {:.1}ms", phase, self.spent_ms(*phase)))
+            .collect();
+        format!("{}, total {:.1}ms", phases.join(", "), self.elapsed_ms())
+    }
+}
+
+// what a ship out of time does: docks if a planet in range has room left, taking the spots already claimed this
+// turn into account, and stays put otherwise. Neither needs any navigating nor risks a collision
+pub fn fallback_move(ship: &Ship, game_map: &GameMap, docking: &mut HashMap<i32, usize>) -> Command {
+    let my_id = game_map.get_me().id;
+    let planet = game_map.all_planets().iter().find(|p| {
+        ship.in_dock_range(p) && p.owner.is_none_or(|owner| owner == my_id)
+            && p.open_docks() > docking.get(&p.id).cloned().unwrap_or(0)
+    });
+    match planet {
+        Some(planet) => {
+            *docking.entry(planet.id).or_insert(0) += 1;
+            ship.dock(planet)
+        }
+        None => Command::Stay(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use time::PreciseTime;
+
+    use hlt::command::Command;
+    use hlt::entity::{GameState, Planet, Position, Ship};
+    use hlt::game::Game;
+    use hlt::game_map::GameMap;
+    use hlt::player::Player;
+    use strategy::budget::{fallback_move, Phase, TimeBudget};
+
+    #[test]
+    fn test_time_budget() {
+        let mut budget = TimeBudget::new(PreciseTime::now(), 0.0, 1e9);
+        budget.enter(Phase::Prediction);
+        budget.enter(Phase::Navigation);
+        budget.end();
+        budget.end();
+        assert!(budget.spent_ms(Phase::Prediction) >= 0.0);
+        assert_eq!(0.0, budget.spent_ms(Phase::Assignment));
+        assert!(!budget.hard_expired());
+        let report = budget.report();
+        assert!(report.starts_with("prediction ") && report.contains(", navigation ") && report.contains(", total "));
+    }
+
+    #[test]
+    fn test_fallback_move() {
+        let game = Game::offline(0, 240, 160);
+        let ships: Vec<Ship> = (0..3).map(|id| Ship::new(id, 0, Position(47.0, 52.0 + id as f64))).collect();
+        let far = Ship::new(3, 0, Position(100.0, 100.0));
+        let state = GameState {
+            players: vec![Player {
+                id: 0,
+                ships: ships.iter().cloned().chain(Some(far.clone())).collect(),
+                strength: 0.0,
+            }],
+            planets: vec![Planet::new(0, Position(50.0, 50.0), 1000, 2.0, 2, 1000)],
+        };
+        let game_map = GameMap::new(&game, state);
+        let mut docking = HashMap::new();
+        assert_eq!(Command::Dock(0, 0), fallback_move(&ships[0], &game_map, &mut docking));
+        assert_eq!(Command::Dock(1, 0), fallback_move(&ships[1], &game_map, &mut docking));
+        // the planet is full now
+        assert_eq!(Command::Stay(), fallback_move(&ships[2], &game_map, &mut docking));
+        assert_eq!(Command::Stay(), fallback_move(&far, &game_map, &mut docking));
+    }
+}
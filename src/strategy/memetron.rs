@@ -0,0 +1,720 @@
+use std::cmp::max;
+use std::collections::HashMap;
+
+use time::PreciseTime;
+
+use hlt::angle::Angle;
+use hlt::combat::{engage, hp_after, local_engagement, Skirmish, ENGAGEMENT_TURNS};
+use hlt::command::Command;
+use hlt::config::Configs;
+use hlt::constants::{DOCK_RADIUS, DOCK_TURNS, FUDGE, MAX_SPEED, SHIP_RADIUS};
+use hlt::entity::{commitment, Entity, Planet, Position, Ship};
+use hlt::game_map::GameMap;
+use hlt::logging::Logger;
+use strategy::budget::{fallback_move, Phase, TimeBudget};
+use strategy::moves::{Move, ShipMoves};
+use strategy::prediction::EnemyPrediction;
+use strategy::Strategy;
+
+// the bot itself: every ship weighs docking, raiding, defending and intercepting against each other and the best
+// moves across the fleet are handed out first
+pub struct Memetron {
+    logger: Logger,
+    configs: Configs,
+    // where enemy ships are expected to go, and how well each way of guessing it has done so far
+    prediction: EnemyPrediction,
+}
+
+// share of the soft time limit enemy prediction may take before its costlier models are given up on
+const PREDICTION_SHARE: f64 = 0.25;
+
+// opponents with more than half as many ships as we have, i.e. the ones to play against
+pub fn relevant_opponents(game_map: &GameMap) -> usize {
+    let my_id = game_map.get_me().id;
+    let my_ship_count = game_map.get_me().all_ships().len();
+    game_map
+        .state
+        .players
+        .iter()
+        .filter(|p| p.id != my_id)
+        .filter(|p| p.all_ships().len() * 2 > my_ship_count)
+        .count()
+}
+
+// planets nobody owns, and ours with room left
+pub fn planets_to_dock<'a>(game_map: &'a GameMap) -> Vec<&'a Planet> {
+    let my_id = game_map.get_me().id;
+    game_map
+        .all_planets()
+        .iter()
+        .filter(|p| {
+            !p.is_owned() || (p.is_owned() && p.owner.unwrap() == my_id && p.open_docks() > 0)
+        })
+        .collect()
+}
+
+impl Memetron {
+    pub fn new(mut logger: Logger, configs: Configs) -> Memetron {
+        logger.log(&format!("configs:\n{}", configs));
+        let prediction = EnemyPrediction::new(&configs);
+        Memetron {
+            logger,
+            configs,
+            prediction,
+        }
+    }
+
+    pub fn with_prediction(mut self, prediction: EnemyPrediction) -> Memetron {
+        self.prediction = prediction;
+        self
+    }
+}
+
+impl Strategy for Memetron {
+    fn on_turn(&mut self, game_map: &GameMap, turn: usize, start: PreciseTime) -> Vec<Command> {
+        let logger = &mut self.logger;
+        let configs = &self.configs;
+        let mut budget = TimeBudget::new(start, configs.time_cutoff_ms, configs.move_time_cutoff_ms);
+        let my_id = game_map.get_me().id;
+        let mut command_queue: Vec<Command> = Vec::new();
+
+        // set playercount-dependent params
+        let relevant_opponents = relevant_opponents(game_map);
+        let preferences = configs.preferences(relevant_opponents);
+
+        let ships = game_map.get_me().all_ships();
+        {
+            let ship_ids = ships
+                .iter()
+                .map(|s| s.id.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+            logger.log(&format!("turn {}, my ships: {}", turn, ship_ids));
+        }
+
+        let planets_to_dock = planets_to_dock(game_map);
+
+        let enemy_docked_ships = game_map.enemy_docked_ships();
+        let enemy_undocked_ships = game_map.enemy_undocked_ships();
+
+        // predict enemy ship movement
+        budget.enter(Phase::Prediction);
+        self.prediction.score(game_map);
+        for player in game_map.state.players.iter().filter(|p| p.id != my_id) {
+            if let Some(tally) = self.prediction.tally(player.id) {
+                logger.log(&format!("  player {} prediction error: {}", player.id, tally));
+            }
+        }
+        let prediction_cutoff_ms = configs.time_cutoff_ms * PREDICTION_SHARE;
+        self.prediction
+            .predict(&enemy_undocked_ships, game_map, &|| budget.elapsed_ms() > prediction_cutoff_ms);
+
+        let ship_count = game_map.my_ships().len();
+        let my_docked_ships = game_map.my_docked_ships();
+
+        let mut ships_to_order = vec![];
+        let mut attempted_commands: HashMap<i32, i32> = HashMap::new();
+        // Ignore ships that are in the process of (un)docking
+        for ship in ships {
+            if ship.is_undocked() {
+                attempted_commands.insert(ship.id, 0);
+                ships_to_order.push(ship);
+            } else if !ship.is_docked() {
+                logger.log(&format!(
+                    "  ship {} will remain {}",
+                    ship.id,
+                    ship.docking_status
+                ));
+                ship.command.set(Some(Command::Stay()));
+            }
+        }
+
+        let mut commitment_map: HashMap<i32, Vec<i32>> = HashMap::new();
+        for ship in game_map.enemy_ships() {
+            commitment_map.insert(ship.id, vec![]);
+        }
+
+        budget.enter(Phase::MoveGeneration);
+        let mut all_ship_moves: Vec<ShipMoves> = vec![];
+        for ship in ships_to_order {
+            if budget.soft_expired() {
+                logger.log(&format!("timeout break in shipmove creation loop {:.0}", budget.elapsed_ms()));
+                break;
+            }
+            all_ship_moves.push(ShipMoves::new(
+                ship,
+                game_map,
+                &planets_to_dock,
+                &enemy_docked_ships,
+                &enemy_undocked_ships,
+                configs,
+                preferences,
+            ))
+        }
+
+        let strongest_enemy_fleet = game_map
+            .state
+            .players
+            .iter()
+            .filter(|p| p.id != my_id)
+            .map(|p| p.all_ships().len())
+            .max()
+            .unwrap();
+        let should_flee = game_map.state.players.len() > 2
+            && strongest_enemy_fleet as f64 > game_map.get_me().all_ships().len() as f64 * configs.flee_threshold;
+
+        let planet_to_destroy: Option<&Planet> = None; /*{
+            let enemy_ships = game_map.enemy_ships();
+            let my_ships = game_map.my_ships();
+            let mut attackable_planets: Vec<(&Planet, i32)> = game_map
+                .all_planets()
+                .into_iter()
+                .filter(|p| // target not my planets
+                        p.owner.is_none() || p.owner.unwrap() != my_id)
+                .filter(|p| {
+                    // filter out planets I couldn't destroy this turn
+                    // this is pretty much never possible TT
+                    let possible_damage = my_ships
+                        .iter()
+                        .filter(|s| s.distance_to_surface(*p) < MAX_SPEED as f64)
+                        .map(|s| s.hp - s.projected_damage_taken(&game_map))
+                        .fold(0, |acc, hp| acc + hp);
+                    //disabled for now
+                    possible_damage >= p.hp
+                })
+                .map(|p| {
+                    // map of planet, total damage dealt to enemies
+                    (
+                        p,
+                        enemy_ships
+                            .iter()
+                            .map(|s| p.damage_from_explosion(s))
+                            .fold(0, |acc, dmg| acc + dmg),
+                    )
+                })
+                // destroy planet only if damage dealt would be 1.2x cost to destroy
+                .filter(|&(p, d)| d as f64 > p.hp as f64 * 0.01)
+                .collect();
+            let planet = attackable_planets
+                .into_iter()
+                .max_by(|&(p1, dmg1), &(p2, dmg2)| {
+                    (dmg1 as f64 / p1.hp as f64)
+                        .partial_cmp(&(dmg2 as f64 / p2.hp as f64))
+                        .unwrap()
+                });
+            match planet {
+                Some((p, d)) => {
+                    p.doomed.set(true);
+                    Some(p)
+                }
+                None => None,
+            }
+        };*/
+        // when destroying a planet, use its commitment as total hp of my ships
+        // committed to its
+        // destruction
+
+        let mut commands_issued = 0;
+        let mut break_command = -1;
+        while game_map.my_ships().iter().any(|s| !s.commanded()) && break_command != commands_issued {
+            break_command = commands_issued;
+
+            // next: only recalc if the move would have been affected, which right now
+            // should just
+            // be if the commitment level of the move target changed
+            budget.enter(Phase::Assignment);
+            for s_m in &mut all_ship_moves {
+                s_m.recombine_deqs();
+                s_m.recalculate_all_moves(game_map, &commitment_map, configs, preferences);
+                s_m.sort_moves();
+                s_m.refresh_best_move();
+            }
+
+            // break executed at end if command issued
+            loop {
+                let (ship_id, command) = {
+                    // command docked ship
+                    if let Some(ship) = game_map
+                        .my_docked_ships()
+                        .into_iter()
+                        .find(|s| !s.commanded() && s.is_docked())
+                    {
+                        if should_flee && !holds_ground(ship, game_map) {
+                            logger.log(&format!("  ship {} will undock to flee", ship.id));
+                            (ship.id, Some(ship.undock()))
+                        } else {
+                            logger.log(&format!("  ship {} will remain DOCKED", ship.id));
+                            (ship.id, Some(Command::Stay()))
+                        }
+
+                    // find the current undocked ship which has the best move to make
+                    } else if let Some(ship_to_move) = all_ship_moves
+                        .iter()
+                            // ?????
+                            //.filter(|s_m| s_m.remaining_moves() > 0)
+                            .filter(|s_m| s_m.remaining_moves() > 1)
+                            .min_by(|s_m1, s_m2| {
+                                s_m1.best_move()
+                                    .value()
+                                    .partial_cmp(&s_m2.best_move().value())
+                                    .unwrap()
+                            }) {
+                        budget.enter(Phase::Navigation);
+                        let command = if should_flee && !holds_ground(ship_to_move.ship, game_map) {
+                            flee(ship_to_move.ship, game_map, logger)
+                        } else if let Some(p) = planet_to_destroy.filter(|p| {
+                            p.commitment() < p.hp && p.distance_to_surface(ship_to_move.ship) < MAX_SPEED as f64
+                        }) {
+                            kamikaze_planet(ship_to_move.ship, p, logger)
+                        } else {
+                            try_move(
+                                ship_to_move,
+                                game_map,
+                                &enemy_undocked_ships,
+                                &my_docked_ships,
+                                relevant_opponents,
+                                &mut commitment_map,
+                                configs,
+                                logger,
+                            )
+                        };
+                        budget.enter(Phase::Assignment);
+                        (ship_to_move.ship.id, command)
+
+                    // there are no ships left to command
+                    } else {
+                        break;
+                    }
+                };
+
+                match command {
+                    Some(command) => {
+                        match command {
+                            Command::Stay() => {}
+                            _ => command_queue.push(command),
+                        }
+                        let ship: &Ship = game_map.get_ship(ship_id);
+                        ship.command.set(Some(command));
+                        if let Some(index) = all_ship_moves.iter().position(|s_m| s_m.ship.id == ship.id) {
+                            all_ship_moves.remove(index);
+                        };
+                        if let Command::Thrust(_s_id, speed, angle) = command {
+                            ship.set_velocity(
+                                speed as f64 * (angle as f64).to_radians().cos(),
+                                speed as f64 * (angle as f64).to_radians().sin(),
+                            );
+                        }
+                        commands_issued += 1;
+                        break;
+                    }
+                    None => if attempted_commands.contains_key(&ship_id) {
+                        *attempted_commands.get_mut(&ship_id).unwrap() += 1;
+                        if attempted_commands[&ship_id] >= max(2000 / ship_count, 30) as i32 {
+                            logger.log(&format!("  ship {} will Stay, move not found", ship_id));
+                            game_map
+                                .get_ship(ship_id)
+                                .command
+                                .set(Some(Command::Stay()));
+                            let index = all_ship_moves
+                                .iter()
+                                .position(|s_m| s_m.ship.id == ship_id)
+                                .unwrap();
+                            all_ship_moves.remove(index);
+                            commands_issued += 1;
+                            break;
+                        }
+                        all_ship_moves
+                            .iter_mut()
+                            .find(|s_m| s_m.ship.id == ship_id)
+                            .unwrap()
+                            .update_best_move();
+                    },
+                }
+
+                if budget.hard_expired() {
+                    logger.log(&format!("timeout break in inner loop {:.0}", budget.elapsed_ms()));
+                    break;
+                }
+            } // loop
+            if budget.soft_expired() {
+                logger.log(&format!("timeout break in outer loop {:.0}", budget.elapsed_ms()));
+                break;
+            }
+        }
+        budget.end();
+
+        // ships left over, whether out of time or out of moves, still get something cheap to do
+        let mut docking: HashMap<i32, usize> = HashMap::new();
+        for ship in game_map.my_undocked_ships().into_iter().filter(|s| !s.commanded()) {
+            let command = fallback_move(ship, game_map, &mut docking);
+            logger.log(&format!("  ship {} falls back to {:?}", ship.id, command));
+            ship.command.set(Some(command));
+            if command != Command::Stay() {
+                command_queue.push(command);
+            }
+        }
+        for command in command_queue.iter() {
+            logger.log(&command.encode().to_string());
+        }
+        logger.log(&format!("  timing: {}\n\n", budget.report()));
+        command_queue
+    }
+}
+
+// a ship whose side wins the fight it's in stays to fight even when everyone else flees
+fn holds_ground(ship: &Ship, game_map: &GameMap) -> bool {
+    let fight = local_engagement(ship, game_map);
+    fight.their_ships > 0 && fight.won()
+}
+
+pub fn flee(ship: &Ship, game_map: &GameMap, logger: &mut Logger) -> Option<Command> {
+    let margin = 1.7;
+    let small_margin = SHIP_RADIUS + FUDGE;
+    let center = game_map.center();
+    let ship_angle = Angle::between(&center, &ship.get_position()).to_degrees();
+    // the map split into quarters by its diagonals, as the angles from the center to the corners bounding them
+    let corner = |x: f64, y: f64| Angle::radians((y * center.1).atan2(x * center.0)).to_degrees();
+    let north_range = (corner(-1.0, -1.0), corner(1.0, -1.0));
+    let south_range = (corner(1.0, 1.0), corner(-1.0, 1.0));
+    let west_range = (corner(-1.0, 1.0), corner(-1.0, -1.0));
+    let destination = if ship_angle <= south_range.1 && ship_angle >= south_range.0 {
+        Position(game_map.width() - small_margin, game_map.height() - margin)
+    } else if ship_angle < west_range.1 && ship_angle > west_range.0 {
+        Position(margin, game_map.height() - small_margin)
+    } else if ship_angle < north_range.1 && ship_angle > north_range.0 {
+        Position(small_margin, margin)
+    } else {
+        Position(game_map.width() - margin, small_margin)
+    };
+    let speed_angle: Option<(i32, i32)> = ship.smart_navigate(
+        &destination,
+        game_map,
+        game_map.obstacles_for_flee(ship),
+        true,
+    );
+    match speed_angle {
+        Some((speed, angle)) => {
+            logger.log(&format!(
+                "  ship {} : speed: {}, angle: {} fleeing to {}",
+                ship.id,
+                speed,
+                angle,
+                destination,
+            ));
+            Some(ship.thrust(speed, angle))
+        }
+        _ => {
+            logger.log(&format!(
+                "  --- failed to find path to flee for ship {}",
+                ship.id
+            ));
+            None
+        }
+    }
+}
+
+pub fn kamikaze_planet(ship: &Ship, planet: &Planet, logger: &mut Logger) -> Option<Command> {
+    let speed = MAX_SPEED;
+    let angle = ship.calculate_angle_between(planet).round() as i32;
+    planet.committed_ships.set(ship.hp + planet.commitment());
+    logger.log(&format!(
+        "  ship {} : speed: {}, angle: {}, target planet: {} for death star",
+        ship.id,
+        speed,
+        angle,
+        planet.id
+    ));
+    Some(ship.thrust(speed, angle))
+}
+
+// whether the ship would be destroyed next turn flying at the given thrust, with the enemy it's going after still
+// standing. Everything else keeps the velocity it has, i.e. our commanded ships and the enemy predictions
+fn dies_in_vain(ship: &Ship, enemy_ship: &Ship, speed: i32, angle: i32, game_map: &GameMap) -> bool {
+    let mut skirmish = Skirmish::around(game_map, &ship.get_position(), 1);
+    skirmish.set_velocity(
+        ship.id,
+        (
+            speed as f64 * (angle as f64).to_radians().cos(),
+            speed as f64 * (angle as f64).to_radians().sin(),
+        ),
+    );
+    skirmish.run(1);
+    skirmish.hp(ship.id) == Some(0) && skirmish.hp(enemy_ship.id).is_none_or(|hp| hp > 0)
+}
+
+// TODO form group out of ships committed to same target?
+#[allow(clippy::too_many_arguments)]
+pub fn try_move(
+    ship_to_move: &ShipMoves,
+    game_map: &GameMap,
+    enemy_undocked_ships: &Vec<&Ship>,
+    my_docked_ships: &Vec<&Ship>,
+    relevant_opponents: usize,
+    commitment_map: &mut HashMap<i32, Vec<i32>>,
+    configs: &Configs,
+    logger: &mut Logger,
+) -> Option<Command> {
+    let ship = ship_to_move.ship;
+    let command = match *ship_to_move.best_move() {
+        Move::DockMove(planet, v) => {
+            let destination = &ship.closest_point_to(planet, 1.0);
+            // check if nearby enemies with commitment == 0
+            // problem: commitment is still 0 because defender has not gone yet
+            // TODO: maybe move this to dock_value
+            let nearby_enemies = enemy_undocked_ships.iter().any(|e_s| {
+                e_s.distance_to(destination) < 0.5 * (DOCK_TURNS * MAX_SPEED * 2) as f64
+                    && commitment(e_s, commitment_map, configs) < 0.05
+            });
+            // TODO need cheese defense. if cheesed, move to sit on ally ship?
+
+            // no command if all dock spots are claimed (maybe move this to dock_value), or if a ship would spawn
+            // before we could arrive
+            if (planet.num_docking_spots - (planet.committed_ships.get() + planet.docked_ships.len() as i32)) == 0
+                || nearby_enemies
+                || (planet.turns_until_spawn() as f64)
+                    < (ship.distance_to_surface(planet) + DOCK_RADIUS) / MAX_SPEED as f64
+            {
+                None
+
+            // if close enough to dock
+            } else if ship.in_dock_range(planet) {
+                planet.committed_ships.set(planet.committed_ships.get() + 1);
+                logger.log(&format!(
+                    "  Ship {} docking to {} value: {}",
+                    ship.id,
+                    planet.id,
+                    v
+                ));
+                Some(ship.dock(planet))
+
+            // otherwise, fly towards planet
+            } else {
+                let speed_angle: Option<(i32, i32)> = ship.smart_navigate(
+                    destination,
+                    game_map,
+                    game_map.obstacles_for_dock(ship),
+                    false,
+                );
+                match speed_angle {
+                    Some((speed, angle)) => {
+                        logger.log(&format!(
+                            "  ship {} : speed: {}, angle: {}, target: {}, target planet: {} value: {}",
+                            ship.id,
+                            speed,
+                            angle,
+                            destination,
+                            planet.id,
+                            v
+                        ));
+                        planet.increment_committed_ships();
+                        Some(ship.thrust(speed, angle))
+                    }
+                    _ => {
+                        logger.log(&format!(
+                            "  --- failed to find path to planet {} for ship {}",
+                            planet.id,
+                            ship.id
+                        ));
+                        None
+                    }
+                }
+            }
+        }
+
+        Move::RaidMove(enemy_ship, v) => if ship.distance_to_surface(enemy_ship) < MAX_SPEED as f64
+            && hp_after(ship, game_map, 2) == 0
+        {
+            // kamikaze if ship would be destroyed in the next two turns of fighting anyway
+            let destination = enemy_ship.get_position();
+            let speed_angle = ship.smart_navigate(
+                &destination,
+                game_map,
+                game_map.obstacles_for_raid_kamikaze(ship),
+                false,
+            );
+            match speed_angle {
+                Some((speed, angle)) => {
+                    logger.log(&format!(
+                        "  ship {} (hp {}) speed: {}, angle: {}, dest: {} dist: {} will kamikaze to attack ship {} v: {}, PDT: {}",
+                        ship.id,
+                        ship.hp,
+                        speed,
+                        angle,
+                        destination,
+                        ship.distance_to_surface(enemy_ship),
+                        enemy_ship.id,
+                        v,
+                        ship.projected_damage_taken_two_turns(game_map)
+                    ));
+                    commitment_map
+                        .get_mut(&enemy_ship.id)
+                        .unwrap()
+                        .push(ship.hp);
+                    Some(ship.thrust(speed, angle))
+                }
+                None => None,
+            }
+        } else {
+            let destination = &enemy_ship.get_position();
+            let speed_angle: Option<(i32, i32)> = ship.smart_navigate(
+                destination,
+                game_map,
+                if engage(&ship.defenders(game_map), &enemy_ship.defenders(game_map), ENGAGEMENT_TURNS).won() {
+                    // the fight against the target's defenders would be won, so fly through their fire
+                    game_map.obstacles_for_raid_ignore_defenders(ship, enemy_ship)
+                } else {
+                    game_map.obstacles_for_raid(ship)
+                },
+                true,
+            );
+            match speed_angle {
+                Some((speed, angle)) if dies_in_vain(ship, enemy_ship, speed, angle, game_map) => {
+                    logger.log(&format!(
+                        "  ship {} won't raid ship {}, it would be destroyed first",
+                        ship.id,
+                        enemy_ship.id
+                    ));
+                    None
+                }
+                Some((speed, angle)) => {
+                    logger.log(&format!(
+                        "  ship {} (hp {}) : speed: {}, angle: {}, target: {}, dist: {} target ship: {} value: {}, PDT: {}",
+                        ship.id,
+                        ship.hp,
+                        speed,
+                        angle,
+                        destination,
+                        ship.distance_to_surface(enemy_ship),
+                        enemy_ship.id,
+                        v,
+                        ship.projected_damage_taken_two_turns(game_map)
+                    ));
+                    commitment_map
+                        .get_mut(&enemy_ship.id)
+                        .unwrap()
+                        .push(ship.hp);
+                    Some(ship.thrust(speed, angle))
+                }
+                _ => {
+                    logger.log(&format!(
+                        "  --- failed to find path to ship {} for ship {}",
+                        enemy_ship.id,
+                        ship.id
+                    ));
+                    None
+                }
+            }
+        },
+
+        Move::DefendMove(enemy_ship, v) => {
+            if my_docked_ships.is_empty() {
+                // if we get here, it probably means we have no docked ships and there
+                // aren't any good attack or dock targets. Probably screwed
+                Some(Command::Stay())
+            } else {
+                let ship_to_defend = enemy_ship.nearest_entity(my_docked_ships.as_slice());
+
+                let (dx, dy) = (
+                    (enemy_ship.get_position().0 - ship_to_defend.get_position().0),
+                    (enemy_ship.get_position().1 - ship_to_defend.get_position().1),
+                );
+                let magnitude = f64::sqrt(dx.powi(2) + dy.powi(2));
+                let destination = if relevant_opponents > 1 {
+                    Position(
+                        ship_to_defend.get_position().0 + (dx / magnitude),
+                        ship_to_defend.get_position().1 + (dy / magnitude),
+                    )
+                } else {
+                    Position(
+                        // TODO: what is the best strategy here
+                        // (enemy_ship.get_position().0 + ship_to_defend.get_position().0) / 2.0,
+                        // (enemy_ship.get_position().1 + ship_to_defend.get_position().1) / 2.0,
+                        // (ship_to_defend.get_position().0 + (dx / magnitude)),
+                        // (ship_to_defend.get_position().1 + (dy / magnitude)),
+                        enemy_ship.get_position().0 - (dx / magnitude),
+                        enemy_ship.get_position().1 - (dy / magnitude),
+                    )
+                };
+                let speed_angle: Option<(i32, i32)> = ship.smart_navigate(
+                    &destination,
+                    game_map,
+                    game_map.obstacles_for_defend(ship),
+                    true,
+                );
+                match speed_angle {
+                    Some((speed, angle)) if dies_in_vain(ship, enemy_ship, speed, angle, game_map) => {
+                        logger.log(&format!(
+                            "  ship {} won't defend from ship {}, it would be destroyed first",
+                            ship.id,
+                            enemy_ship.id
+                        ));
+                        None
+                    }
+                    Some((speed, angle)) => {
+                        logger.log(&format!(
+                            "  ship {} : speed: {}, angle: {}, target: {}, defending {} from: {} value: {}",
+                            ship.id,
+                            speed,
+                            angle,
+                            destination,
+                            ship_to_defend.id,
+                            enemy_ship.id,
+                            v
+                        ));
+                        commitment_map
+                            .get_mut(&enemy_ship.id)
+                            .unwrap()
+                            .push(ship.hp);
+                        Some(ship.thrust(speed, angle))
+                    }
+                    _ => {
+                        logger.log(&format!(
+                            "  --- failed to find path to ship {} for ship {}",
+                            enemy_ship.id,
+                            ship.id
+                        ));
+                        None
+                    }
+                }
+            }
+        }
+
+        Move::InterceptMove(enemy_ship, v) => {
+            // TODO: move to enemy projected position?
+            let destination = enemy_ship.get_position();
+            let speed_angle: Option<(i32, i32)> = ship.smart_navigate(
+                &destination,
+                game_map,
+                game_map.obstacles_for_intercept(ship),
+                true,
+            );
+            match speed_angle {
+                Some((speed, angle)) => {
+                    logger.log(&format!(
+                        "  ship {} : speed: {}, angle: {}, target: {}, intercepting {} value: {}",
+                        ship.id,
+                        speed,
+                        angle,
+                        destination,
+                        enemy_ship.id,
+                        v
+                    ));
+                    commitment_map
+                        .get_mut(&enemy_ship.id)
+                        .unwrap()
+                        .push(ship.hp);
+                    Some(ship.thrust(speed, angle))
+                }
+                _ => {
+                    logger.log(&format!(
+                        "  --- failed to find path to ship {} for ship {}",
+                        enemy_ship.id,
+                        ship.id
+                    ));
+                    None
+                }
+            }
+        }
+    };
+    command
+}
@@ -0,0 +1,169 @@
+use time::PreciseTime;
+
+use hlt::command::Command;
+use hlt::config::Configs;
+use hlt::entity::{Entity, GameState, Obstacle, Position, Ship};
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::logging::Logger;
+
+pub mod attacker;
+pub mod budget;
+pub mod memetron;
+pub mod moves;
+pub mod prediction;
+pub mod rush;
+pub mod settler;
+
+use strategy::attacker::Attacker;
+use strategy::memetron::Memetron;
+use strategy::rush::Rush;
+use strategy::settler::Settler;
+
+// how far from its target's surface an attacking ship stops, well inside weapon range
+pub const ATTACK_DISTANCE: f64 = 2.0;
+
+pub const STRATEGIES: [&str; 4] = ["memetron", "settler", "rush", "attacker"];
+
+// a bot: given the map at the start of a turn, the commands to send for it. start is when the map line was read,
+// which is when the engine's clock for the turn started too
+pub trait Strategy {
+    fn on_turn(&mut self, game_map: &GameMap, turn: usize, start: PreciseTime) -> Vec<Command>;
+}
+
+// one of STRATEGIES, memetron playing with the given configs and logging through the given logger
+pub fn by_name(name: &str, configs: Configs, logger: Logger) -> Option<Box<dyn Strategy>> {
+    match name {
+        "memetron" => Some(Box::new(Memetron::new(logger, configs))),
+        "settler" => Some(Box::new(Settler)),
+        "rush" => Some(Box::new(Rush)),
+        "attacker" => Some(Box::new(Attacker)),
+        _ => None,
+    }
+}
+
+// a thrust towards destination. The thrust is recorded as the ship's velocity so that ships commanded after it
+// steer clear of where it is going
+pub fn fly_to(ship: &Ship, destination: &Position, game_map: &GameMap, obstacles: Vec<Obstacle>) -> Option<Command> {
+    let (speed, angle) = ship.smart_navigate(destination, game_map, obstacles, false)?;
+    ship.set_velocity(
+        speed as f64 * (angle as f64).to_radians().cos(),
+        speed as f64 * (angle as f64).to_radians().sin(),
+    );
+    Some(ship.thrust(speed, angle))
+}
+
+// heads for the nearest enemy ship, avoiding collisions but not fights
+pub fn attack(ship: &Ship, game_map: &GameMap) -> Option<Command> {
+    let target = game_map.nearest_ship(&ship.get_position(), |s| s.owner_id != ship.owner_id)?;
+    if ship.distance_to_surface(target) < ATTACK_DISTANCE {
+        return None;
+    }
+    let destination = ship.closest_point_to(target, ATTACK_DISTANCE);
+    fly_to(ship, &destination, game_map, game_map.obstacles_for_defend(ship))
+}
+
+// plays every turn the engine sends until it closes the input
+pub fn play<S: Strategy + ?Sized>(game: &Game, strategy: &mut S, logger: &mut Logger) {
+    let mut turn: usize = 0;
+    let gs = GameState {
+        players: vec![],
+        planets: vec![],
+    };
+    let mut game_map = GameMap::new(game, gs);
+    loop {
+        turn += 1;
+        let (next_map, start) = match game.update_map(&game_map) {
+            Ok(update) => update,
+            Err(_) if game.finished() => break,
+            Err(e) => {
+                // skip the turn rather than crash, the next map line may well be fine
+                logger.log(&format!("Could not parse map on turn {}: {}", turn, e));
+                game.send_command_queue(&game_map, vec![], logger);
+                continue;
+            }
+        };
+        game_map = next_map;
+        let commands = strategy.on_turn(&game_map, turn, start);
+        game.send_command_queue(&game_map, commands, logger);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::{self, Write};
+    use std::rc::Rc;
+
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use engine::runner::{run_match, MatchSettings};
+    use hlt::config::Configs;
+    use hlt::game::Game;
+    use hlt::logging::Logger;
+    use hlt::transport::Streams;
+    use strategy::memetron::Memetron;
+    use strategy::play;
+
+    // a writer the test can still read after handing it to the game
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // plays the scripted map lines after a handshake as player 0, returning every line the bot wrote
+    fn play_script(map_lines: &[&str]) -> Vec<String> {
+        let mut input = "0\n240 160\n".to_string();
+        for line in map_lines.iter() {
+            input.push_str(line);
+            input.push('\n');
+        }
+        let output = Output::default();
+        let game = Game::with_transport("test", Box::new(Streams::new(io::Cursor::new(input), output.clone())));
+        let log_path = env::temp_dir().join(format!("scripted_game_{}.txt", process::id()));
+        let mut logger = Logger::with_path(log_path.to_str().unwrap());
+        let mut memetron = Memetron::new(logger.clone(), Configs::default());
+        play(&game, &mut memetron, &mut logger);
+        fs::remove_file(&log_path).unwrap();
+        let written = String::from_utf8(output.0.borrow().clone()).unwrap();
+        written.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_scripted_game() {
+        let undocked = "2 0 1 0 50 50 255 0 0 0 0 0 0 1 1 1 200 150 255 0 0 0 0 0 0 1 0 55 50 1000 3 3 0 1000 0 0 0";
+        let docking = "2 0 1 0 50 50 255 0 0 1 0 5 0 1 1 1 200 150 255 0 0 0 0 0 0 1 0 55 50 1000 3 3 0 1000 1 0 1 0";
+        // the first map line is the one sent before the game starts, the garbled one is skipped with no commands
+        let lines = play_script(&[undocked, undocked, "2 0 x", docking, undocked]);
+        assert_eq!(vec!["test", "d 0 0", "", "", "d 0 0"], lines);
+    }
+
+    #[test]
+    fn test_builtin_match() {
+        let settings = MatchSettings {
+            seed: Some(5),
+            width: 240,
+            height: 160,
+            bots: vec!["builtin:settler".to_string(), "builtin:rush".to_string(), "builtin:attacker".to_string()],
+            timeouts: false,
+            replay_directory: None,
+            compress_replay: false,
+        };
+        let result = run_match(&settings).unwrap();
+        let names: Vec<&str> = result.players.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(vec!["settler", "rush", "attacker"], names);
+        assert!(result.players.iter().all(|p| !p.failed));
+        // the rushers settle it long before the turn limit
+        assert!(result.turns < 100);
+    }
+}
@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use hlt::config::{Configs, Preferences};
+use hlt::entity::{Planet, Ship};
+use hlt::game_map::GameMap;
+
+#[derive(Debug)]
+pub enum MoveType {
+    DockMove,
+    RaidMove,
+    DefendMove,
+    InterceptMove,
+}
+
+#[derive(Debug)]
+pub enum Move<'a> {
+    DockMove(&'a Planet, f64),
+    RaidMove(&'a Ship, f64),
+    DefendMove(&'a Ship, f64),
+    InterceptMove(&'a Ship, f64),
+}
+
+impl<'a> Move<'a> {
+    pub fn value(&self) -> f64 {
+        match *self {
+            Move::DockMove(_p, v) => v,
+            Move::RaidMove(_s, v) => v,
+            Move::DefendMove(_s, v) => v,
+            Move::InterceptMove(_s, v) => v,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn id(&self) -> i32 {
+        match *self {
+            Move::DockMove(p, _v) => p.id,
+            Move::RaidMove(s, _v) => s.id,
+            Move::DefendMove(s, _v) => s.id,
+            Move::InterceptMove(s, _v) => s.id,
+        }
+    }
+
+    pub fn move_type(&self) -> MoveType {
+        match *self {
+            Move::DockMove(_p, _v) => MoveType::DockMove,
+            Move::RaidMove(_s, _v) => MoveType::RaidMove,
+            Move::DefendMove(_s, _v) => MoveType::DefendMove,
+            Move::InterceptMove(_s, _v) => MoveType::InterceptMove,
+        }
+    }
+
+    pub fn recalculate(
+        &mut self,
+        ship: &Ship,
+        game_map: &GameMap,
+        commitment_map: &HashMap<i32, Vec<i32>>,
+        configs: &Configs,
+        preferences: &Preferences,
+    ) {
+        match *self {
+            Move::DockMove(p, ref mut v) => *v = preferences.dock * ship.dock_value(p, game_map, configs),
+            Move::RaidMove(s, ref mut v) => {
+                *v = preferences.raid * ship.raid_value(s, game_map, commitment_map, configs)
+            }
+            Move::DefendMove(s, ref mut v) => {
+                *v = preferences.defend * ship.defense_value(s, game_map, commitment_map, configs)
+            }
+            Move::InterceptMove(s, ref mut v) => {
+                *v = preferences.intercept * ship.intercept_value(s, commitment_map, configs)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ShipMoves<'a> {
+    pub ship: &'a Ship,
+    pub dock_moves: Vec<Move<'a>>,
+    pub raid_moves: Vec<Move<'a>>,
+    pub defend_moves: Vec<Move<'a>>,
+    pub intercept_moves: Vec<Move<'a>>,
+    pub deqd_dock_moves: Vec<Move<'a>>,
+    pub deqd_raid_moves: Vec<Move<'a>>,
+    pub deqd_defend_moves: Vec<Move<'a>>,
+    pub deqd_intercept_moves: Vec<Move<'a>>,
+    pub best_move: MoveType,
+}
+
+impl<'a> ShipMoves<'a> {
+    pub fn new<'b>(
+        ship: &'b Ship,
+        game_map: &'b GameMap,
+        planets_to_dock: &Vec<&'b Planet>,
+        enemy_docked_ships: &Vec<&'b Ship>,
+        enemy_undocked_ships: &Vec<&'b Ship>,
+        configs: &Configs,
+        preferences: &Preferences,
+    ) -> ShipMoves<'b> {
+        let mut dummy_commitment_map: HashMap<i32, Vec<i32>> = HashMap::new();
+        for ship in game_map.enemy_ships() {
+            dummy_commitment_map.insert(ship.id, vec![]);
+        }
+        let dock_moves: Vec<Move> = planets_to_dock
+            .iter()
+            .map(|planet| {
+                let mut m = Move::DockMove(planet, 0.0);
+                m.recalculate(ship, game_map, &dummy_commitment_map, configs, preferences);
+                m
+            })
+            .collect();
+        let raid_moves: Vec<Move> = enemy_docked_ships
+            .iter()
+            .map(|enemy_ship| {
+                let mut m = Move::RaidMove(enemy_ship, 0.0);
+                m.recalculate(ship, game_map, &dummy_commitment_map, configs, preferences);
+                m
+            })
+            .collect();
+        // make defend move function of friendly ship? create defend move only if one
+        // of closer ships
+        let defend_moves: Vec<Move> = enemy_undocked_ships
+            .iter()
+            .map(|enemy_ship| {
+                let mut m = Move::DefendMove(enemy_ship, 0.0);
+                m.recalculate(ship, game_map, &dummy_commitment_map, configs, preferences);
+                m
+            })
+            .collect();
+        // TODO: disable intercept?
+        let intercept_moves: Vec<Move> = enemy_undocked_ships
+            .iter()
+            .map(|enemy_ship| {
+                let mut m = Move::InterceptMove(enemy_ship, 0.0);
+                m.recalculate(ship, game_map, &dummy_commitment_map, configs, preferences);
+                m
+            })
+            .collect();
+        // let mut intercept_moves: Vec<Move> = vec![];
+        let deqd_dock_moves = vec![];
+        let deqd_raid_moves = vec![];
+        let deqd_defend_moves = vec![];
+        let deqd_intercept_moves = vec![];
+        let best_move = MoveType::DockMove;
+        let mut s_m = ShipMoves {
+            ship,
+            dock_moves,
+            raid_moves,
+            defend_moves,
+            intercept_moves,
+            deqd_dock_moves,
+            deqd_raid_moves,
+            deqd_defend_moves,
+            deqd_intercept_moves,
+            best_move,
+        };
+        s_m.sort_moves();
+        s_m.refresh_best_move();
+        s_m
+    }
+
+    // moves must be sorted by value within their type before calling
+    pub fn update_best_move(&mut self) {
+        match self.best_move {
+            MoveType::DockMove => self.deqd_dock_moves.push(self.dock_moves.remove(0)),
+            MoveType::RaidMove => self.deqd_raid_moves.push(self.raid_moves.remove(0)),
+            MoveType::DefendMove => self.deqd_defend_moves.push(self.defend_moves.remove(0)),
+            MoveType::InterceptMove => self.deqd_intercept_moves
+                .push(self.intercept_moves.remove(0)),
+        };
+        self.refresh_best_move();
+    }
+
+    pub fn recombine_deqs(&mut self) {
+        while !self.deqd_dock_moves.is_empty() {
+            self.dock_moves.push(self.deqd_dock_moves.remove(0));
+        }
+        while !self.deqd_raid_moves.is_empty() {
+            self.raid_moves.push(self.deqd_raid_moves.remove(0));
+        }
+        while !self.deqd_defend_moves.is_empty() {
+            self.defend_moves.push(self.deqd_defend_moves.remove(0));
+        }
+        while !self.deqd_intercept_moves.is_empty() {
+            self.intercept_moves
+                .push(self.deqd_intercept_moves.remove(0));
+        }
+    }
+
+    pub fn recalculate_all_moves(
+        &mut self,
+        game_map: &GameMap,
+        commitment_map: &HashMap<i32, Vec<i32>>,
+        configs: &Configs,
+        preferences: &Preferences,
+    ) {
+        for d_m in &mut self.dock_moves {
+            d_m.recalculate(self.ship, game_map, commitment_map, configs, preferences);
+        }
+        for r_m in &mut self.raid_moves {
+            r_m.recalculate(self.ship, game_map, commitment_map, configs, preferences);
+        }
+        for d_m in &mut self.defend_moves {
+            d_m.recalculate(self.ship, game_map, commitment_map, configs, preferences);
+        }
+        for i_m in &mut self.intercept_moves {
+            i_m.recalculate(self.ship, game_map, commitment_map, configs, preferences);
+        }
+    }
+
+    pub fn refresh_best_move(&mut self) {
+        self.best_move = {
+            vec![
+                self.dock_moves.first(),
+                self.raid_moves.first(),
+                self.defend_moves.first(),
+                self.intercept_moves.first(),
+            ].into_iter()
+                .flatten()
+                .min_by(|move1, move2| {
+                    move1.value().partial_cmp(&move2.value()).unwrap()
+                })
+                .unwrap()
+                .move_type()
+        };
+    }
+
+    pub fn sort_moves(&mut self) {
+        let sort_fn = |m1: &Move, m2: &Move| -> Ordering { m1.value().partial_cmp(&m2.value()).unwrap() };
+        self.dock_moves.sort_by(&sort_fn);
+        self.raid_moves.sort_by(&sort_fn);
+        self.defend_moves.sort_by(&sort_fn);
+        self.intercept_moves.sort_by(&sort_fn);
+    }
+
+    pub fn remaining_moves(&self) -> usize {
+        self.dock_moves.len() + self.raid_moves.len() + self.defend_moves.len() + self.intercept_moves.len()
+    }
+
+    pub fn best_move(&self) -> &Move<'_> {
+        match self.best_move {
+            MoveType::DockMove => self.dock_moves.first().unwrap(),
+            MoveType::RaidMove => self.raid_moves.first().unwrap(),
+            MoveType::DefendMove => self.defend_moves.first().unwrap(),
+            MoveType::InterceptMove => self.intercept_moves.first().unwrap(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn as_string(&self) -> String {
+        format!(
+            "
+ShipMoves {{
+    ship_id: {}
+    best_move: {:#?}
+    dock_moves: {}
+    raid_moves: {}
+    defend_moves: {}
+    intercept_moves: {}
+    }}",
+            self.ship.id,
+            self.best_move(),
+            self.dock_moves
+                .iter()
+                .map(|m| format!("planet_id: {}, value: {}", m.id(), m.value()))
+                .fold(String::new(), |acc, s| { acc + "\n        " + &s }),
+            self.raid_moves
+                .iter()
+                .map(|m| format!("ship_id: {}, value: {}", m.id(), m.value()))
+                .fold(String::new(), |acc, s| { acc + "\n        " + &s }),
+            self.defend_moves
+                .iter()
+                .map(|m| format!("ship_id: {}, value: {}", m.id(), m.value()))
+                .fold(String::new(), |acc, s| { acc + "\n        " + &s }),
+            self.intercept_moves
+                .iter()
+                .map(|m| format!("ship_id: {}, value: {}", m.id(), m.value()))
+                .fold(String::new(), |acc, s| { acc + "\n        " + &s }),
+        )
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use hlt::config::Configs;
+use hlt::entity::{Entity, Position, Ship};
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use strategy::memetron::{planets_to_dock, relevant_opponents};
+use strategy::moves::{Move, ShipMoves};
+
+// how much the latest error counts towards a ship's recent error with a model, the rest being the turns before
+const RECENT_WEIGHT: f64 = 0.5;
+
+// "adaptive" picks a model per ship, any other name always uses that model
+pub const ADAPTIVE: &str = "adaptive";
+pub const PREDICTORS: [&str; 4] = ["chase", "constant_velocity", "dock", "ship_moves"];
+
+// a way of guessing how an enemy ship moves next turn
+pub trait EnemyPredictor {
+    // works out anything shared by every ship this turn, giving up once out_of_time says so
+    fn prepare(&mut self, _game_map: &GameMap, _out_of_time: &dyn Fn() -> bool) {}
+
+    // the velocity the ship is expected to have next turn, None if the model has nothing to go on
+    fn velocity(&self, ship: &Ship, game_map: &GameMap) -> Option<(f64, f64)>;
+}
+
+fn velocity_to<T: Entity>(ship: &Ship, target: &T, game_map: &GameMap) -> (f64, f64) {
+    let (speed, angle) = ship.route_to(target, game_map);
+    (
+        speed as f64 * (angle as f64).to_radians().cos(),
+        speed as f64 * (angle as f64).to_radians().sin(),
+    )
+}
+
+// heads for the nearest ship that isn't its own, at full speed
+pub struct Chase;
+
+impl EnemyPredictor for Chase {
+    fn velocity(&self, ship: &Ship, game_map: &GameMap) -> Option<(f64, f64)> {
+        let target = game_map.nearest_ship(&ship.get_position(), |s| s.owner_id != ship.owner_id)?;
+        Some(velocity_to(ship, target, game_map))
+    }
+}
+
+// keeps going the way it went last turn
+pub struct ConstantVelocity;
+
+impl EnemyPredictor for ConstantVelocity {
+    fn velocity(&self, ship: &Ship, _game_map: &GameMap) -> Option<(f64, f64)> {
+        ship.estimated_velocity()
+    }
+}
+
+// heads for the nearest planet it could dock at, and stops once in range of it
+pub struct NearestPlanetDock;
+
+impl EnemyPredictor for NearestPlanetDock {
+    fn velocity(&self, ship: &Ship, game_map: &GameMap) -> Option<(f64, f64)> {
+        let planet = game_map
+            .all_planets()
+            .iter()
+            .filter(|p| p.owner.is_none() || (p.owner == Some(ship.owner_id) && p.open_docks() > 0))
+            .min_by(|p1, p2| {
+                ship.distance_to_surface(*p1)
+                    .partial_cmp(&ship.distance_to_surface(*p2))
+                    .unwrap()
+            })?;
+        if ship.in_dock_range(planet) {
+            Some((0.0, 0.0))
+        } else {
+            Some(velocity_to(ship, &ship.closest_point_to(planet, 1.0), game_map))
+        }
+    }
+}
+
+// does whatever our own bot would do in its place: the best of its ShipMoves, worked out from its owner's point of
+// view. By far the most expensive model, so it stops working out destinations once out of time
+pub struct OwnMoves {
+    configs: Configs,
+    // where each ship's best move takes it this turn
+    destinations: HashMap<i32, Position>,
+}
+
+impl OwnMoves {
+    pub fn new(configs: &Configs) -> OwnMoves {
+        OwnMoves {
+            configs: configs.clone(),
+            destinations: HashMap::new(),
+        }
+    }
+}
+
+impl EnemyPredictor for OwnMoves {
+    fn prepare(&mut self, game_map: &GameMap, out_of_time: &dyn Fn() -> bool) {
+        self.destinations.clear();
+        let my_id = game_map.get_me().id;
+        for player in game_map.state.players.iter().filter(|p| p.id != my_id) {
+            let game = Game::offline(player.id as usize, game_map.width() as i32, game_map.height() as i32);
+            let their_map = game_map.as_player(&game);
+            let planets = planets_to_dock(&their_map);
+            let (docked, undocked) = (their_map.enemy_docked_ships(), their_map.enemy_undocked_ships());
+            // nothing for them to do, and ShipMoves needs at least one move
+            if planets.is_empty() && docked.is_empty() && undocked.is_empty() {
+                continue;
+            }
+            let preferences = self.configs.preferences(relevant_opponents(&their_map));
+            for ship in their_map.my_undocked_ships() {
+                if out_of_time() {
+                    return;
+                }
+                let ship_moves =
+                    ShipMoves::new(ship, &their_map, &planets, &docked, &undocked, &self.configs, preferences);
+                let destination = match *ship_moves.best_move() {
+                    Move::DockMove(planet, _) => ship.closest_point_to(planet, 1.0),
+                    Move::RaidMove(target, _) | Move::DefendMove(target, _) | Move::InterceptMove(target, _) => {
+                        target.get_position()
+                    }
+                };
+                self.destinations.insert(ship.id, destination);
+            }
+        }
+    }
+
+    fn velocity(&self, ship: &Ship, game_map: &GameMap) -> Option<(f64, f64)> {
+        let destination = self.destinations.get(&ship.id)?;
+        Some(velocity_to(ship, destination, game_map))
+    }
+}
+
+// every error scored so far, as total error and number of predictions for each model and then for the ones picked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tally {
+    models: Vec<(f64, usize)>,
+    picked: (f64, usize),
+}
+
+fn mean(&(total, count): &(f64, usize)) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
+impl Default for Tally {
+    fn default() -> Tally {
+        Tally {
+            models: vec![(0.0, 0); PREDICTORS.len()],
+            picked: (0.0, 0),
+        }
+    }
+}
+
+impl Tally {
+    pub fn add(&mut self, other: &Tally) {
+        for (t, o) in self.models.iter_mut().zip(other.models.iter()).chain(Some((&mut self.picked, &other.picked))) {
+            t.0 += o.0;
+            t.1 += o.1;
+        }
+    }
+
+    // each model's mean error, in the order of PREDICTORS
+    pub fn means(&self) -> Vec<f64> {
+        self.models.iter().map(mean).collect()
+    }
+
+    pub fn picked_mean(&self) -> f64 {
+        mean(&self.picked)
+    }
+
+    pub fn picked_count(&self) -> usize {
+        self.picked.1
+    }
+}
+
+// e.g. "chase 3.21, constant_velocity 1.05, dock 4.10, ship_moves 2.52, picked 0.98 over 120 predictions"
+impl fmt::Display for Tally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, error) in PREDICTORS.iter().zip(self.means()) {
+            write!(f, "{} {:.2}, ", name, error)?;
+        }
+        write!(f, "picked {:.2} over {} predictions", self.picked_mean(), self.picked_count())
+    }
+}
+
+// Guesses where enemy ships go next turn with every model, picking for each ship the model that has lately been
+// closest for it. Each turn, before predicting, last turn's guesses are scored against where the ships are now by
+// the distance between the two
+pub struct EnemyPrediction {
+    predictors: Vec<Box<dyn EnemyPredictor>>,
+    // index into predictors of the model always picked, or None to pick per ship
+    fixed: Option<usize>,
+    // per ship, each model's recent error, None until it has been scored
+    recent: HashMap<i32, Vec<Option<f64>>>,
+    // per ship, where each model expected it to be and the model picked
+    pending: HashMap<i32, (Vec<Option<Position>>, usize)>,
+    // per player, the errors for their ships
+    tallies: HashMap<i32, Tally>,
+}
+
+impl EnemyPrediction {
+    pub fn new(configs: &Configs) -> EnemyPrediction {
+        EnemyPrediction {
+            predictors: vec![
+                Box::new(Chase),
+                Box::new(ConstantVelocity),
+                Box::new(NearestPlanetDock),
+                Box::new(OwnMoves::new(configs)),
+            ],
+            fixed: None,
+            recent: HashMap::new(),
+            pending: HashMap::new(),
+            tallies: HashMap::new(),
+        }
+    }
+
+    // always predicts with the named model, or picks per ship for ADAPTIVE
+    pub fn with_model(mut self, name: &str) -> Result<EnemyPrediction, String> {
+        self.fixed = match PREDICTORS.iter().position(|p| *p == name) {
+            Some(index) => Some(index),
+            None if name == ADAPTIVE => None,
+            None => return Err(format!("unknown prediction model {:?}", name)),
+        };
+        Ok(self)
+    }
+
+    pub fn score(&mut self, game_map: &GameMap) {
+        let models = self.predictors.len();
+        for (id, (predicted, picked)) in self.pending.drain() {
+            let ship = match game_map.find_ship(id) {
+                Some(ship) => ship,
+                None => continue,
+            };
+            let tally = self.tallies.entry(ship.owner_id).or_default();
+            let recent = self.recent.entry(id).or_insert_with(|| vec![None; models]);
+            for (m, position) in predicted.iter().enumerate() {
+                if let Some(position) = *position {
+                    let error = ship.get_position().distance_to(&position);
+                    recent[m] = Some(match recent[m] {
+                        Some(previous) => RECENT_WEIGHT * error + (1.0 - RECENT_WEIGHT) * previous,
+                        None => error,
+                    });
+                    tally.models[m].0 += error;
+                    tally.models[m].1 += 1;
+                    if m == picked {
+                        tally.picked.0 += error;
+                        tally.picked.1 += 1;
+                    }
+                }
+            }
+        }
+        // ships that are gone won't be predicted again
+        self.recent.retain(|&id, _| game_map.find_ship(id).is_some());
+    }
+
+    // sets each ship's velocity to the picked model's prediction, leaving it alone if that model has none
+    pub fn predict(&mut self, ships: &[&Ship], game_map: &GameMap, out_of_time: &dyn Fn() -> bool) {
+        for predictor in self.predictors.iter_mut() {
+            predictor.prepare(game_map, out_of_time);
+        }
+        for ship in ships {
+            let velocities: Vec<Option<(f64, f64)>> = self.predictors
+                .iter()
+                .map(|predictor| predictor.velocity(ship, game_map))
+                .collect();
+            let picked = match self.fixed.or_else(|| self.pick(ship.id, &velocities)) {
+                Some(picked) => picked,
+                None => continue,
+            };
+            if let Some((v_x, v_y)) = velocities[picked] {
+                ship.set_velocity(v_x, v_y);
+            }
+            let Position(x, y) = ship.get_position();
+            let predicted = velocities
+                .iter()
+                .map(|v| v.map(|(v_x, v_y)| Position(x + v_x, y + v_y)))
+                .collect();
+            self.pending.insert(ship.id, (predicted, picked));
+        }
+    }
+
+    // the model with a prediction that has lately been closest for the ship. Models not scored for it yet come
+    // after the rest, in the order they're listed
+    fn pick(&self, id: i32, velocities: &[Option<(f64, f64)>]) -> Option<usize> {
+        let recent = self.recent.get(&id);
+        let error = |m: usize| recent.and_then(|r| r[m]).unwrap_or(f64::MAX);
+        (0..velocities.len())
+            .filter(|&m| velocities[m].is_some())
+            .min_by(|&m1, &m2| error(m1).partial_cmp(&error(m2)).unwrap())
+    }
+
+    // the errors for the player's ships so far, None before any have been scored
+    pub fn tally(&self, player_id: i32) -> Option<&Tally> {
+        self.tallies.get(&player_id)
+    }
+
+    // the errors for everyone's ships
+    pub fn total(&self) -> Tally {
+        let mut total = Tally::default();
+        for tally in self.tallies.values() {
+            total.add(tally);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::angle::Angle;
+    use hlt::config::Configs;
+    use hlt::entity::{GameState, Planet, Position, Ship};
+    use hlt::game::Game;
+    use hlt::game_map::GameMap;
+    use hlt::player::Player;
+    use strategy::prediction::{Chase, ConstantVelocity, EnemyPrediction, EnemyPredictor, NearestPlanetDock};
+
+    fn game_map(game: &Game, ships: Vec<Ship>) -> GameMap<'_> {
+        let players = (0..2)
+            .map(|id| Player {
+                id,
+                ships: ships.iter().filter(|s| s.owner_id == id).cloned().collect(),
+                strength: 0.0,
+            })
+            .collect();
+        let planets = vec![Planet::new(0, Position(100.0, 20.0), 1000, 5.0, 3, 1000)];
+        GameMap::new(game, GameState { players, planets })
+    }
+
+    #[test]
+    fn test_estimates() {
+        let mut ship = Ship::new(1, 1, Position(10.0, 10.0));
+        assert_eq!(None, ship.estimated_velocity());
+        ship.positions = vec![Position(10.0, 10.0), Position(12.0, 10.0), Position(12.0, 14.0)];
+        assert_eq!(Some((0.0, 4.0)), ship.estimated_velocity());
+        assert_eq!(Some((-2.0, 4.0)), ship.estimated_acceleration());
+        assert_eq!(Some(Angle::degrees(90.0)), ship.estimated_heading());
+        ship.positions.push(Position(12.0, 14.0));
+        assert_eq!(None, ship.estimated_heading());
+    }
+
+    #[test]
+    fn test_predictors() {
+        let game = Game::offline(0, 240, 160);
+        let mut enemy = Ship::new(1, 1, Position(50.0, 50.0));
+        enemy.positions.insert(0, Position(47.0, 50.0));
+        let game_map = game_map(&game, vec![Ship::new(0, 0, Position(20.0, 50.0)), enemy.clone()]);
+        let (v_x, v_y) = Chase.velocity(&enemy, &game_map).unwrap();
+        assert!((v_x + 7.0).abs() < 1e-9 && v_y.abs() < 1e-9);
+        assert_eq!(Some((3.0, 0.0)), ConstantVelocity.velocity(&enemy, &game_map));
+        let (v_x, v_y) = NearestPlanetDock.velocity(&enemy, &game_map).unwrap();
+        assert!(v_x > 0.0 && v_y < 0.0);
+    }
+
+    #[test]
+    fn test_enemy_prediction() {
+        let game = Game::offline(0, 240, 160);
+        let mine = Ship::new(0, 0, Position(20.0, 50.0));
+        let mut enemy = Ship::new(1, 1, Position(50.0, 50.0));
+        enemy.positions.insert(0, Position(47.0, 50.0));
+        let mut prediction = EnemyPrediction::new(&Configs::default());
+        assert!(prediction.tally(1).is_none());
+
+        // with nothing scored yet the first model with a prediction is picked
+        let before = game_map(&game, vec![mine.clone(), enemy.clone()]);
+        prediction.predict(&before.enemy_undocked_ships(), &before, &|| false);
+        assert!(before.get_ship(1).velocity_x.get() < 0.0);
+
+        // the enemy kept going rather than chasing, so from then on it's expected to keep going
+        enemy.positions.push(Position(53.0, 50.0));
+        let after = game_map(&game, vec![mine.clone(), enemy]);
+        prediction.score(&after);
+        prediction.predict(&after.enemy_undocked_ships(), &after, &|| false);
+        assert_eq!(3.0, after.get_ship(1).velocity_x.get());
+        let report = prediction.tally(1).unwrap().to_string();
+        assert!(report.starts_with("chase 10.00, constant_velocity 0.00, "), "{}", report);
+        assert!(report.ends_with("picked 10.00 over 1 predictions"), "{}", report);
+        assert_eq!(report, prediction.total().to_string());
+
+        // once the enemy is gone its errors are forgotten
+        prediction.score(&game_map(&game, vec![mine]));
+        assert!(prediction.recent.is_empty());
+
+        // a fixed model is always the one used
+        let mut prediction = EnemyPrediction::new(&Configs::default()).with_model("dock").unwrap();
+        prediction.predict(&after.enemy_undocked_ships(), &after, &|| false);
+        assert!(after.get_ship(1).velocity_y.get() < 0.0);
+        assert!(EnemyPrediction::new(&Configs::default()).with_model("psychic").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use time::PreciseTime;
+
+use hlt::command::Command;
+use hlt::entity::{Entity, Position, Ship};
+use hlt::game_map::GameMap;
+use strategy::{fly_to, Strategy, ATTACK_DISTANCE};
+
+// the cheese: never docks, and the whole fleet flies as one pack at the enemy miner closest to it, falling back on
+// whatever enemy ship is closest once nobody is mining
+pub struct Rush;
+
+fn fleet_center(ships: &[&Ship]) -> Position {
+    let n = ships.len() as f64;
+    let (x, y) = ships.iter().fold((0.0, 0.0), |(x, y), s| {
+        let position = s.get_position();
+        (x + position.0, y + position.1)
+    });
+    Position(x / n, y / n)
+}
+
+impl Strategy for Rush {
+    fn on_turn(&mut self, game_map: &GameMap, _turn: usize, _start: PreciseTime) -> Vec<Command> {
+        let my_ships = game_map.my_undocked_ships();
+        let enemy_ships = game_map.enemy_ships();
+        let miners = game_map.enemy_docked_ships();
+        let targets = if miners.is_empty() { &enemy_ships } else { &miners };
+        if my_ships.is_empty() || targets.is_empty() {
+            return vec![];
+        }
+        let target = fleet_center(&my_ships).nearest_entity(targets);
+        my_ships
+            .into_iter()
+            .filter_map(|ship| {
+                let destination = ship.closest_point_to(target, ATTACK_DISTANCE);
+                fly_to(ship, &destination, game_map, game_map.obstacles_for_defend(ship))
+            })
+            .collect()
+    }
+}
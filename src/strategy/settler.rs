@@ -0,0 +1,46 @@
+use time::PreciseTime;
+
+use hlt::command::Command;
+use hlt::entity::{Entity, Planet, Ship};
+use hlt::game_map::GameMap;
+use strategy::{attack, fly_to, Strategy};
+
+// the starter kit bot: every ship docks on the nearest planet with a free spot, and only goes after the enemy
+// once there is nowhere left to dock
+pub struct Settler;
+
+fn has_room(planet: &Planet, my_id: i32) -> bool {
+    let claimed = planet.docked_ships.len() as i32 + planet.committed_ships.get();
+    planet.owner.is_none_or(|owner| owner == my_id) && claimed < planet.num_docking_spots
+}
+
+fn settle(ship: &Ship, game_map: &GameMap) -> Option<Command> {
+    let my_id = game_map.get_me().id;
+    let mut planets: Vec<&Planet> = game_map
+        .all_planets()
+        .iter()
+        .filter(|p| has_room(p, my_id))
+        .collect();
+    planets.sort_by(|p1, p2| {
+        ship.distance_to_surface(*p1)
+            .partial_cmp(&ship.distance_to_surface(*p2))
+            .unwrap()
+    });
+    let planet = planets.first()?;
+    planet.increment_committed_ships();
+    if ship.in_dock_range(planet) {
+        Some(ship.dock(planet))
+    } else {
+        fly_to(ship, &ship.closest_point_to(*planet, 1.0), game_map, game_map.obstacles_for_dock(ship))
+    }
+}
+
+impl Strategy for Settler {
+    fn on_turn(&mut self, game_map: &GameMap, _turn: usize, _start: PreciseTime) -> Vec<Command> {
+        game_map
+            .my_undocked_ships()
+            .into_iter()
+            .filter_map(|ship| settle(ship, game_map).or_else(|| attack(ship, game_map)))
+            .collect()
+    }
+}
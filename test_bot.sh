@@ -1,7 +1,8 @@
 #!/usr/bin/env bash
 set -e
 
-cargo rustc --release -q -- -Awarnings -A dead_code
+cargo rustc --release -q --bin MyBot -- -Awarnings -A dead_code
+cargo build --release -q --bin tournament
 
 if ls log_*.txt 1> /dev/null 2>&1; then
   rm -f log_*.txt
@@ -16,51 +17,26 @@ if ls replays/*.hlt 1> /dev/null 2>&1; then
   rm -f replays/*.hlt
 fi
 
-# print config constants
-cat src/hlt/constants.rs | grep -A500 'CONFIGURATIONS' | tail -n+2
+# extra MyBot arguments, a config file and/or key=value overrides, e.g. "tuned.json flee_threshold=2.5"
+CONFIG=""
 
-FILENAME=.bot_tests
-[ -e $FILENAME ] && rm -f $FILENAME
-touch $FILENAME
-BOT_1="target/release/MyBot"
-BOT_2="bots/ipostv6"
+# print the effective config
+target/release/MyBot --print-config $CONFIG
+
+BOT_1="target/release/MyBot $CONFIG"
+BOT_2="ipostv6"
 GAMES=20
 PARALLEL=2
-GAMES=$((GAMES / PARALLEL))
 
-#run $PARALLEL games at a time
+# plays GAMES games, PARALLEL at a time, on maps from 240 x 160 to 384 x 256
 START_TIME=$(date +%s)
-for j in $(seq 1 $PARALLEL);
-do
-  {
-    for i in $(seq 1 $GAMES);
-    do
-      # largest board is 384 x 256, smallest is 240 x 160
-      SIZE_Y=$(awk -v min=160 -v max=256 'BEGIN{srand(); print int(min+rand()*(max-min+1))}')
-      SIZE_X=$((SIZE_Y * 3 / 2 ))
-      #./halite_osx -d "$SIZE_X $SIZE_Y" "RUST_BACKTRACE=1 $BOT_1" "$BOT_2" "$BOT_2" "$BOT_2" >> $FILENAME
-      ./halite_osx -d "$SIZE_X $SIZE_Y" "RUST_BACKTRACE=1 $BOT_1" "$BOT_2" >> $FILENAME
-      #./halite_osx -d "$SIZE_X $SIZE_Y" "target/release/MyBot" "bots/cheesebotv2" >> $FILENAME
-      #./halite_osx -d "$SIZE_X $SIZE_Y" "RUST_BACKTRACE=1 target/release/MyBot" "./bots/ipostv3" "./bots/ipostv3" "./bots/ipostv3" >> $FILENAME
-    done
-  } &
-done
-wait
+target/release/tournament -g $GAMES -j $PARALLEL "RUST_BACKTRACE=1 $BOT_1" $BOT_2
+#target/release/tournament -g $GAMES -j $PARALLEL -f 4 "RUST_BACKTRACE=1 $BOT_1" $BOT_2
+#target/release/tournament -g $GAMES -j $PARALLEL "$BOT_1" cheesebotv2
+#target/release/tournament -g $GAMES -j $PARALLEL -f mixed "RUST_BACKTRACE=1 $BOT_1" ipostv3
 END_TIME=$(date +%s)
 echo "Test time: $((END_TIME - START_TIME))s"
 
-#mv replays/*.hlt .
-#rm -f *.hlt
-
-echo "rank #1 $(cat .bot_tests | grep "Player #0.\+came in rank #1" | wc -l) times out of $((GAMES * PARALLEL)) games"
-echo "rank #2 $(cat .bot_tests | grep "Player #0.\+came in rank #2" | wc -l) times out of $((GAMES * PARALLEL)) games"
-echo "rank #3 $(cat .bot_tests | grep "Player #0.\+came in rank #3" | wc -l) times out of $((GAMES * PARALLEL)) games"
-echo "rank #4 $(cat .bot_tests | grep "Player #0.\+came in rank #4" | wc -l) times out of $((GAMES * PARALLEL)) games"
-#echo "Player #0 won $(cat .bot_tests | grep "Player #0.\+came in rank #1" | wc -l) times out of $((GAMES * PARALLEL)) games"
-#echo "Player #1 won $(cat .bot_tests | grep "Player #1.\+came in rank #1" | wc -l) times out of $((GAMES * PARALLEL)) games"
-#echo "Player #2 won $(cat .bot_tests | grep "Player #2.\+came in rank #1" | wc -l) times out of $((GAMES * PARALLEL)) games"
-#echo "Player #3 won $(cat .bot_tests | grep "Player #3.\+came in rank #1" | wc -l) times out of $((GAMES * PARALLEL)) games"
-echo "$(ls *-*.log 2> /dev/null | wc -l) Failures found"
 echo -ne "\0007"
 #say "test complete"
 